@@ -0,0 +1,71 @@
+/*!
+Animate display properties.
+
+A `Tween` animates an observable property, such as the alpha or
+position of a display object, from a start value to an end value
+over a duration, following an `Easing` function. Values are
+interpolated through the `Interpolate` trait, implemented for
+`f64`, `f32`, `Vector2d` and `Color`.
+
+```ignore
+use agera::{animation::*, timer::Duration};
+
+let fade_out = Tween::new(&display_object.alpha_property(), 0.0, Duration::from_millis(300))
+    .easing(Easing::QuadOut)
+    .play();
+fade_out.finished().await;
+```
+
+Animations are composed through `Sequence`, `Parallel` and `Repeat`,
+for which the `Animation` trait provides shorthands:
+
+```ignore
+let slide = Tween::new(&display_object.position_property(), Vector2d(100.0, 0.0), Duration::from_millis(500));
+let spin = Tween::new(&display_object.rotation_radians_property(), PI, Duration::from_millis(500));
+slide.with(spin).then(Delay(Duration::from_millis(200))).repeat(3).yoyo().play();
+```
+
+A `Spring` animates a value towards a target through a physical
+simulation with configurable stiffness, damping and mass. Springs
+retarget smoothly when the target changes while moving.
+
+```ignore
+let spring = Spring::new(&display_object.position_property(), SpringConfig::critically_damped(300.0));
+spring.set_velocity(release_velocity);
+spring.set_target(Vector2d::zero());
+```
+
+A `Timeline` is a keyframed animation of the children of a
+`MovieClip`, with labeled frames, and can be loaded from JSON.
+
+```ignore
+let clip = MovieClip::new(Timeline::load_async("app://animations/intro.json").await?);
+clip.add_child(&ball);
+clip.add_frame_script(47, |clip| clip.goto_and_play("loop"));
+clip.play();
+```
+
+Played animations, springs and clips are driven by the animation ticker and
+follow the mock clock in `agera::timer::testing`.
+*/
+
+mod easing;
+pub use self::easing::*;
+
+mod interpolate;
+pub use self::interpolate::*;
+
+mod playback;
+pub use self::playback::*;
+
+mod tween;
+pub use self::tween::*;
+
+mod group;
+pub use self::group::*;
+
+mod spring;
+pub use self::spring::*;
+
+mod timeline;
+pub use self::timeline::*;
@@ -0,0 +1,248 @@
+use std::f64::consts::PI;
+use crate::common::*;
+
+/// An easing function, mapping the linear progress of an animation
+/// to an eased progress. Includes the standard set of Robert Penner's
+/// easing functions and cubic Bézier curves as in CSS.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// A cubic Bézier curve from (0, 0) to (1, 1) with the control
+    /// points (x1, y1) and (x2, y2), as in the CSS `cubic-bezier()` function.
+    /// The x coordinates are clamped to the range between 0 and 1.
+    CubicBezier(f64, f64, f64, f64),
+}
+
+const BACK_C1: f64 = 1.70158;
+const BACK_C2: f64 = BACK_C1 * 1.525;
+const BACK_C3: f64 = BACK_C1 + 1.0;
+const ELASTIC_C4: f64 = (2.0 * PI) / 3.0;
+const ELASTIC_C5: f64 = (2.0 * PI) / 4.5;
+
+impl Easing {
+    /// The CSS `ease` curve.
+    pub const EASE: Easing = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    /// The CSS `ease-in` curve.
+    pub const EASE_IN: Easing = Easing::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    /// The CSS `ease-out` curve.
+    pub const EASE_OUT: Easing = Easing::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    /// The CSS `ease-in-out` curve.
+    pub const EASE_IN_OUT: Easing = Easing::CubicBezier(0.42, 0.0, 0.58, 1.0);
+
+    /// Applies the easing function to `t`, which is clamped to
+    /// the range between 0 and 1. The result may overshoot that
+    /// range for back and elastic easings.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Self::Linear => t,
+            Self::QuadIn => power_in(t, 2),
+            Self::QuadOut => power_out(t, 2),
+            Self::QuadInOut => power_in_out(t, 2),
+            Self::CubicIn => power_in(t, 3),
+            Self::CubicOut => power_out(t, 3),
+            Self::CubicInOut => power_in_out(t, 3),
+            Self::QuartIn => power_in(t, 4),
+            Self::QuartOut => power_out(t, 4),
+            Self::QuartInOut => power_in_out(t, 4),
+            Self::QuintIn => power_in(t, 5),
+            Self::QuintOut => power_out(t, 5),
+            Self::QuintInOut => power_in_out(t, 5),
+            Self::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Self::SineOut => (t * PI / 2.0).sin(),
+            Self::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Self::ExpoIn => if t == 0.0 { 0.0 } else { 2f64.powf(10.0 * t - 10.0) },
+            Self::ExpoOut => if t == 1.0 { 1.0 } else { 1.0 - 2f64.powf(-10.0 * t) },
+            Self::ExpoInOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    2f64.powf(20.0 * t - 10.0) / 2.0
+                } else {
+                    (2.0 - 2f64.powf(-20.0 * t + 10.0)) / 2.0
+                }
+            },
+            Self::CircIn => 1.0 - (1.0 - t * t).sqrt(),
+            Self::CircOut => (1.0 - (t - 1.0).powi(2)).sqrt(),
+            Self::CircInOut => {
+                if t < 0.5 {
+                    (1.0 - (1.0 - (2.0 * t).powi(2)).sqrt()) / 2.0
+                } else {
+                    ((1.0 - (-2.0 * t + 2.0).powi(2)).sqrt() + 1.0) / 2.0
+                }
+            },
+            Self::BackIn => BACK_C3 * t * t * t - BACK_C1 * t * t,
+            Self::BackOut => 1.0 + BACK_C3 * (t - 1.0).powi(3) + BACK_C1 * (t - 1.0).powi(2),
+            Self::BackInOut => {
+                if t < 0.5 {
+                    ((2.0 * t).powi(2) * ((BACK_C2 + 1.0) * 2.0 * t - BACK_C2)) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((BACK_C2 + 1.0) * (t * 2.0 - 2.0) + BACK_C2) + 2.0) / 2.0
+                }
+            },
+            Self::ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -2f64.powf(10.0 * t - 10.0) * ((t * 10.0 - 10.75) * ELASTIC_C4).sin()
+                }
+            },
+            Self::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC_C4).sin() + 1.0
+                }
+            },
+            Self::ElasticInOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    -(2f64.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_C5).sin()) / 2.0
+                } else {
+                    (2f64.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_C5).sin()) / 2.0 + 1.0
+                }
+            },
+            Self::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Self::BounceOut => bounce_out(t),
+            Self::BounceInOut => {
+                if t < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+                }
+            },
+            Self::CubicBezier(x1, y1, x2, y2) => cubic_bezier(t, x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2),
+        }
+    }
+}
+
+fn power_in(t: f64, n: i32) -> f64 {
+    t.powi(n)
+}
+
+fn power_out(t: f64, n: i32) -> f64 {
+    1.0 - (1.0 - t).powi(n)
+}
+
+fn power_in_out(t: f64, n: i32) -> f64 {
+    if t < 0.5 {
+        2f64.powi(n - 1) * t.powi(n)
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(n) / 2.0
+    }
+}
+
+fn bounce_out(t: f64) -> f64 {
+    const N1: f64 = 7.5625;
+    const D1: f64 = 2.75;
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+/// Evaluates one coordinate of a cubic Bézier curve from 0 to 1 at parameter `s`.
+fn bezier_coordinate(s: f64, p1: f64, p2: f64) -> f64 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+}
+
+fn bezier_slope(s: f64, p1: f64, p2: f64) -> f64 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * p1 + 6.0 * inverse * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
+
+fn cubic_bezier(x: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    // Find the parameter for `x` through Newton's method,
+    // falling back to bisection.
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier_coordinate(s, x1, x2) - x;
+        if error.abs() < 1e-7 {
+            return bezier_coordinate(s, y1, y2);
+        }
+        let slope = bezier_slope(s, x1, x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..64 {
+        let value = bezier_coordinate(s, x1, x2);
+        if (value - x).abs() < 1e-7 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    bezier_coordinate(s, y1, y2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easing() {
+        let easings = [
+            Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut,
+            Easing::CubicInOut, Easing::QuintOut, Easing::SineInOut, Easing::ExpoInOut,
+            Easing::CircInOut, Easing::BackInOut, Easing::ElasticInOut, Easing::BounceInOut,
+            Easing::EASE, Easing::EASE_IN_OUT,
+        ];
+        for easing in easings {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{easing:?}");
+        }
+        assert!((Easing::QuadIn.apply(0.5) - 0.25).abs() < 1e-9);
+        assert!((Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3) - 0.3).abs() < 1e-4);
+        assert!(Easing::EASE_IN.apply(0.5) < 0.5);
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{Mutex, PoisonError};
+use crate::timer::Duration;
+use super::Animation;
+
+/// Animations played one after another.
+pub struct Sequence {
+    animations: Vec<Box<dyn Animation>>,
+    last_time: Mutex<Option<Duration>>,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self { animations: vec![], last_time: Mutex::new(None) }
+    }
+
+    /// Appends an animation to the sequence, so that chained
+    /// calls to `then()` play in a single flat sequence.
+    pub fn then(mut self, animation: impl Animation) -> Self {
+        self.animations.push(Box::new(animation));
+        self
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Animation for Sequence {
+    fn duration(&self) -> Duration {
+        self.animations.iter().fold(Duration::ZERO, |total, animation| total.saturating_add(animation.duration()))
+    }
+
+    fn seek(&self, time: Duration) {
+        let last_time = self.last_time.lock().unwrap_or_else(PoisonError::into_inner).replace(time);
+        // Seek the animations whose interval overlaps the interval from the last time
+        // to the new time, in playing order, so that animations that were skipped
+        // over still reach their end state.
+        let (low, high) = match last_time {
+            Some(last_time) => (last_time.min(time), last_time.max(time)),
+            None => (Duration::ZERO, time),
+        };
+        let mut intervals = vec![];
+        let mut start = Duration::ZERO;
+        for animation in self.animations.iter() {
+            let end = start.saturating_add(animation.duration());
+            if start <= high && (end > low || last_time.is_none()) {
+                intervals.push((animation, start, end));
+            }
+            start = end;
+        }
+        if last_time.is_some_and(|last_time| time < last_time) {
+            intervals.reverse();
+        }
+        for (animation, start, end) in intervals {
+            animation.seek(time.clamp(start, end) - start);
+        }
+    }
+}
+
+/// Animations played at the same time.
+pub struct Parallel {
+    animations: Vec<Box<dyn Animation>>,
+    last_time: Mutex<Option<Duration>>,
+}
+
+impl Parallel {
+    pub fn new() -> Self {
+        Self { animations: vec![], last_time: Mutex::new(None) }
+    }
+
+    /// Adds an animation to the group, so that chained
+    /// calls to `with()` play in a single flat group.
+    pub fn with(mut self, animation: impl Animation) -> Self {
+        self.animations.push(Box::new(animation));
+        self
+    }
+}
+
+impl Default for Parallel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Animation for Parallel {
+    fn duration(&self) -> Duration {
+        self.animations.iter().map(|animation| animation.duration()).max().unwrap_or(Duration::ZERO)
+    }
+
+    fn seek(&self, time: Duration) {
+        let last_time = self.last_time.lock().unwrap_or_else(PoisonError::into_inner).replace(time);
+        for animation in self.animations.iter() {
+            let duration = animation.duration();
+            // Animations that had already ended stay untouched.
+            if last_time.is_some_and(|last_time| last_time >= duration && time >= duration) {
+                continue;
+            }
+            animation.seek(time.min(duration));
+        }
+    }
+}
+
+/// An animation repeated a number of times or forever,
+/// optionally alternating direction on each repetition.
+pub struct Repeat<A: Animation> {
+    animation: A,
+    count: Option<u32>,
+    yoyo: bool,
+}
+
+impl<A: Animation> Repeat<A> {
+    pub(super) fn new(animation: A, count: Option<u32>) -> Self {
+        Self { animation, count, yoyo: false }
+    }
+
+    /// Plays every other repetition backwards.
+    pub fn yoyo(mut self) -> Self {
+        self.yoyo = true;
+        self
+    }
+}
+
+impl<A: Animation> Animation for Repeat<A> {
+    fn duration(&self) -> Duration {
+        let Some(count) = self.count else {
+            return Duration::MAX;
+        };
+        self.animation.duration().checked_mul(count).unwrap_or(Duration::MAX)
+    }
+
+    fn seek(&self, time: Duration) {
+        let iteration_duration = self.animation.duration();
+        if iteration_duration.is_zero() {
+            self.animation.seek(Duration::ZERO);
+            return;
+        }
+        let mut iteration = time.as_nanos() / iteration_duration.as_nanos();
+        let mut local = Duration::from_nanos((time.as_nanos() % iteration_duration.as_nanos()) as u64);
+        // The end of the last repetition.
+        if self.count.is_some_and(|count| iteration >= count as u128) {
+            iteration = self.count.unwrap().saturating_sub(1) as u128;
+            local = iteration_duration;
+        }
+        if self.yoyo && iteration % 2 == 1 {
+            local = iteration_duration - local;
+        }
+        self.animation.seek(local);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{geom::Vector2d, util::Color};
+
+/// A value that can be interpolated between a start and an end value.
+pub trait Interpolate: Clone + Send + Sync + 'static {
+    /// Returns the value at `t` between `self`, at 0, and `to`, at 1.
+    /// `t` may lie outside that range for overshooting easings.
+    fn interpolate(&self, to: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        self + (to - self) * t as f32
+    }
+}
+
+impl Interpolate for Vector2d {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        Vector2d(self.0.interpolate(&to.0, t), self.1.interpolate(&to.1, t))
+    }
+}
+
+/// Colors are interpolated per channel in sRGB space.
+impl Interpolate for Color {
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        Color::new(
+            self.red().interpolate(&to.red(), t),
+            self.green().interpolate(&to.green(), t),
+            self.blue().interpolate(&to.blue(), t),
+            self.alpha().interpolate(&to.alpha(), t),
+        )
+    }
+}
@@ -0,0 +1,177 @@
+use std::{future::Future, pin::Pin, sync::{Arc, Mutex, PoisonError}, task::{Context, Poll, Waker}};
+use crate::timer::{self, Duration, FreeInterval};
+use super::{Parallel, Repeat, Sequence};
+
+/// The period of the animation ticker driving played animations.
+pub const FRAME_PERIOD: Duration = Duration::from_micros(16_667);
+
+/// An animation whose state is a function of time, such as a `Tween`
+/// or a group of animations.
+pub trait Animation: Send + Sync + 'static {
+    /// The duration of the animation. `Duration::MAX` indicates
+    /// an animation that repeats forever.
+    fn duration(&self) -> Duration;
+
+    /// Applies the state of the animation at `time`, between
+    /// zero and the duration.
+    fn seek(&self, time: Duration);
+
+    /// Repeats the animation `count` times.
+    fn repeat(self, count: u32) -> Repeat<Self> where Self: Sized {
+        Repeat::new(self, Some(count))
+    }
+
+    /// Repeats the animation until it is stopped.
+    fn repeat_forever(self) -> Repeat<Self> where Self: Sized {
+        Repeat::new(self, None)
+    }
+
+    /// Returns a sequence playing this animation and then `next`.
+    fn then(self, next: impl Animation) -> Sequence where Self: Sized {
+        Sequence::new().then(self).then(next)
+    }
+
+    /// Returns a group playing this animation and `other` at the same time.
+    fn with(self, other: impl Animation) -> Parallel where Self: Sized {
+        Parallel::new().with(self).with(other)
+    }
+
+    /// Plays the animation through the animation ticker.
+    fn play(self) -> AnimationHandle where Self: Sized {
+        AnimationHandle::play(Arc::new(self))
+    }
+}
+
+impl Animation for Box<dyn Animation> {
+    fn duration(&self) -> Duration {
+        (**self).duration()
+    }
+
+    fn seek(&self, time: Duration) {
+        (**self).seek(time)
+    }
+}
+
+/// A playing animation, returned by `Animation::play()`.
+#[derive(Clone)]
+pub struct AnimationHandle {
+    inner: Arc<HandleInner>,
+}
+
+struct HandleInner {
+    animation: Arc<dyn Animation>,
+    state: Mutex<HandleState>,
+}
+
+struct HandleState {
+    elapsed: Duration,
+    interval: Option<FreeInterval>,
+    finished: bool,
+    completed: bool,
+    wakers: Vec<Waker>,
+}
+
+impl AnimationHandle {
+    fn play(animation: Arc<dyn Animation>) -> Self {
+        let handle = Self {
+            inner: Arc::new(HandleInner {
+                animation,
+                state: Mutex::new(HandleState {
+                    elapsed: Duration::ZERO,
+                    interval: None,
+                    finished: false,
+                    completed: false,
+                    wakers: vec![],
+                }),
+            }),
+        };
+        handle.inner.animation.seek(Duration::ZERO);
+        if handle.inner.animation.duration().is_zero() {
+            handle.finish(true);
+            return handle;
+        }
+        let interval = timer::free_animation_interval(Box::new({
+            let handle = handle.clone();
+            move |delta| handle.advance(delta)
+        }), FRAME_PERIOD);
+        handle.state().interval = Some(interval);
+        handle
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, HandleState> {
+        self.inner.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn advance(&self, delta: Duration) {
+        let duration = self.inner.animation.duration();
+        let elapsed = {
+            let mut state = self.state();
+            if state.finished {
+                return;
+            }
+            state.elapsed = state.elapsed.saturating_add(delta).min(duration);
+            state.elapsed
+        };
+        self.inner.animation.seek(elapsed);
+        if elapsed >= duration {
+            self.finish(true);
+        }
+    }
+
+    fn finish(&self, completed: bool) {
+        let (interval, wakers) = {
+            let mut state = self.state();
+            if state.finished {
+                return;
+            }
+            state.finished = true;
+            state.completed = completed;
+            (state.interval.take(), std::mem::take(&mut state.wakers))
+        };
+        if let Some(interval) = interval {
+            interval.stop();
+        }
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Stops the animation, leaving it at its current state.
+    pub fn stop(&self) {
+        self.finish(false);
+    }
+
+    /// The time elapsed since the animation started playing.
+    pub fn elapsed(&self) -> Duration {
+        self.state().elapsed
+    }
+
+    /// Indicates whether the animation completed or was stopped.
+    pub fn is_finished(&self) -> bool {
+        self.state().finished
+    }
+
+    /// Returns a future that completes once the animation completes or
+    /// is stopped, resolving to `true` if the animation completed.
+    pub fn finished(&self) -> Finished {
+        Finished { handle: self.clone() }
+    }
+}
+
+/// Future returned by `AnimationHandle::finished()`.
+pub struct Finished {
+    handle: AnimationHandle,
+}
+
+impl Future for Finished {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        let mut state = self.handle.state();
+        if state.finished {
+            return Poll::Ready(state.completed);
+        }
+        state.wakers.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
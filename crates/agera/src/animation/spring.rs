@@ -0,0 +1,354 @@
+use std::{future::Future, pin::Pin, sync::{Arc, Mutex, MutexGuard, PoisonError}, task::{Context, Poll, Waker}};
+use crate::{binding::Property, geom::Vector2d, timer::{self, Duration, FreeInterval}, util::Color};
+use super::{Interpolate, FRAME_PERIOD};
+
+/// A value that can be animated by a spring, as a set of components.
+pub trait SpringValue: Interpolate {
+    fn to_components(&self) -> Vec<f64>;
+    fn from_components(components: &[f64]) -> Self;
+}
+
+impl SpringValue for f64 {
+    fn to_components(&self) -> Vec<f64> {
+        vec![*self]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        components[0]
+    }
+}
+
+impl SpringValue for Vector2d {
+    fn to_components(&self) -> Vec<f64> {
+        vec![self.0, self.1]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        Vector2d(components[0], components[1])
+    }
+}
+
+impl SpringValue for Color {
+    fn to_components(&self) -> Vec<f64> {
+        vec![self.red() as f64, self.green() as f64, self.blue() as f64, self.alpha() as f64]
+    }
+
+    fn from_components(components: &[f64]) -> Self {
+        let channel = |i: usize| components[i].clamp(0.0, 1.0) as f32;
+        Color::new(channel(0), channel(1), channel(2), channel(3))
+    }
+}
+
+/// Physical parameters of a spring.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SpringConfig {
+    pub stiffness: f64,
+    pub damping: f64,
+    pub mass: f64,
+    /// Velocity below which a component may be at rest, in units per second.
+    pub rest_velocity: f64,
+    /// Distance from the target below which a component may be at rest.
+    /// The spring comes to rest once every component is within both thresholds.
+    pub rest_displacement: f64,
+}
+
+impl Default for SpringConfig {
+    fn default() -> Self {
+        Self {
+            stiffness: 170.0,
+            damping: 26.0,
+            mass: 1.0,
+            rest_velocity: 0.001,
+            rest_displacement: 0.001,
+        }
+    }
+}
+
+impl SpringConfig {
+    /// A spring with the given stiffness and a mass of one that returns
+    /// to its target as fast as possible without oscillating.
+    pub fn critically_damped(stiffness: f64) -> Self {
+        Self {
+            stiffness,
+            damping: 2.0 * stiffness.sqrt(),
+            ..Self::default()
+        }
+    }
+
+    /// The ratio of the damping to the critical damping. Springs
+    /// with a ratio below one oscillate around their target.
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping / (2.0 * (self.stiffness * self.mass).sqrt())
+    }
+}
+
+/// Maximum time step of the simulation, for stability with stiff springs.
+const MAX_STEP: f64 = 0.001;
+
+/// A physics-based animation of a value towards a target, driven by the
+/// animation ticker. Changing the target while the spring is moving
+/// retargets smoothly, keeping the current position and velocity.
+/// 
+/// ```ignore
+/// let spring = Spring::new(&display_object.position_property(), SpringConfig::default());
+/// spring.set_target(Vector2d(100.0, 0.0));
+/// spring.finished().await;
+/// ```
+/// 
+/// Cloning a `Spring` clones a reference to the same spring.
+pub struct Spring<T: SpringValue> {
+    inner: Arc<SpringInner<T>>,
+}
+
+impl<T: SpringValue> Clone for Spring<T> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+struct SpringInner<T: SpringValue> {
+    set: Box<dyn Fn(T) + Send + Sync>,
+    state: Mutex<SpringState>,
+}
+
+struct SpringState {
+    config: SpringConfig,
+    position: Vec<f64>,
+    velocity: Vec<f64>,
+    target: Vec<f64>,
+    interval: Option<FreeInterval>,
+    wakers: Vec<Waker>,
+}
+
+/// Stops the interval of a spring dropped while moving.
+impl<T: SpringValue> Drop for SpringInner<T> {
+    fn drop(&mut self) {
+        if let Some(interval) = self.state.get_mut().unwrap_or_else(PoisonError::into_inner).interval.take() {
+            interval.stop();
+        }
+    }
+}
+
+impl<T: SpringValue> Spring<T> {
+    /// Creates a spring animating a property, starting at rest
+    /// at the current value of the property.
+    pub fn new(property: &Property<T>, config: SpringConfig) -> Self {
+        let initial = property.get().expect("The owner of the property was dropped");
+        let property = property.clone();
+        Self::with_setter(initial, config, move |value| property.set(value))
+    }
+
+    /// Creates a spring passing values to a function, starting at rest at `initial`.
+    pub fn with_setter(initial: T, config: SpringConfig, setter: impl Fn(T) + Send + Sync + 'static) -> Self {
+        let position = initial.to_components();
+        Self {
+            inner: Arc::new(SpringInner {
+                set: Box::new(setter),
+                state: Mutex::new(SpringState {
+                    config,
+                    velocity: vec![0.0; position.len()],
+                    target: position.clone(),
+                    position,
+                    interval: None,
+                    wakers: vec![],
+                }),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SpringState> {
+        self.inner.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The current value.
+    pub fn value(&self) -> T {
+        T::from_components(&self.state().position)
+    }
+
+    /// The current velocity, in units per second.
+    pub fn velocity(&self) -> T {
+        T::from_components(&self.state().velocity)
+    }
+
+    pub fn target(&self) -> T {
+        T::from_components(&self.state().target)
+    }
+
+    pub fn config(&self) -> SpringConfig {
+        self.state().config
+    }
+
+    pub fn set_config(&self, config: SpringConfig) {
+        self.state().config = config;
+    }
+
+    /// Sets the velocity, such as the velocity of a drag gesture
+    /// when released, and starts moving the spring.
+    pub fn set_velocity(&self, velocity: T) {
+        self.state().velocity = velocity.to_components();
+        self.start();
+    }
+
+    /// Sets the target the spring moves towards.
+    pub fn set_target(&self, target: T) {
+        self.state().target = target.to_components();
+        self.start();
+    }
+
+    /// Moves the spring to `value` at rest, without animating.
+    pub fn jump_to(&self, value: T) {
+        {
+            let mut state = self.state();
+            state.position = value.to_components();
+            state.target = state.position.clone();
+            state.velocity.iter_mut().for_each(|velocity| *velocity = 0.0);
+        }
+        (self.inner.set)(value);
+        self.settle();
+    }
+
+    /// Indicates whether the spring is at its target with no velocity.
+    pub fn is_at_rest(&self) -> bool {
+        self.state().interval.is_none()
+    }
+
+    /// Stops the spring where it is.
+    pub fn stop(&self) {
+        {
+            let mut state = self.state();
+            state.velocity.iter_mut().for_each(|velocity| *velocity = 0.0);
+            state.target = state.position.clone();
+        }
+        self.settle();
+    }
+
+    /// Returns a future that completes once the spring comes to rest.
+    pub fn finished(&self) -> SpringFinished<T> {
+        SpringFinished { spring: self.clone() }
+    }
+
+    fn start(&self) {
+        if self.state().interval.is_some() {
+            return;
+        }
+        let weak = Arc::downgrade(&self.inner);
+        let interval = timer::free_animation_interval(Box::new(move |delta| {
+            if let Some(inner) = weak.upgrade() {
+                Spring { inner }.step(delta);
+            }
+        }), FRAME_PERIOD);
+        self.state().interval = Some(interval);
+    }
+
+    fn step(&self, delta: Duration) {
+        let (value, at_rest) = {
+            let mut state = self.state();
+            let SpringConfig { stiffness, damping, mass, rest_velocity, rest_displacement } = state.config;
+            let mut remaining = delta.as_secs_f64();
+            while remaining > 0.0 {
+                let dt = remaining.min(MAX_STEP);
+                remaining -= dt;
+                for i in 0..state.position.len() {
+                    // Semi-implicit Euler integration.
+                    let displacement = state.position[i] - state.target[i];
+                    let acceleration = (-stiffness * displacement - damping * state.velocity[i]) / mass;
+                    state.velocity[i] += acceleration * dt;
+                    state.position[i] += state.velocity[i] * dt;
+                }
+            }
+            let at_rest = (0..state.position.len()).all(|i| {
+                state.velocity[i].abs() < rest_velocity && (state.position[i] - state.target[i]).abs() < rest_displacement
+            });
+            if at_rest {
+                state.position = state.target.clone();
+                state.velocity.iter_mut().for_each(|velocity| *velocity = 0.0);
+            }
+            (T::from_components(&state.position), at_rest)
+        };
+        (self.inner.set)(value);
+        if at_rest {
+            self.settle();
+        }
+    }
+
+    /// Stops driving the spring and completes the pending `finished()` futures.
+    fn settle(&self) {
+        let (interval, wakers) = {
+            let mut state = self.state();
+            (state.interval.take(), std::mem::take(&mut state.wakers))
+        };
+        if let Some(interval) = interval {
+            interval.stop();
+        }
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by `Spring::finished()`.
+pub struct SpringFinished<T: SpringValue> {
+    spring: Spring<T>,
+}
+
+impl<T: SpringValue> Future for SpringFinished<T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.spring.state();
+        if state.interval.is_none() {
+            return Poll::Ready(());
+        }
+        state.wakers.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::{animation::*, timer::{testing::MockClock, Duration}};
+
+    #[test]
+    fn test_spring() {
+        let clock = MockClock::install();
+        let values = Arc::new(Mutex::new(vec![]));
+        let spring = Spring::with_setter(0.0, SpringConfig::critically_damped(200.0), {
+            let values = Arc::clone(&values);
+            move |value: f64| values.lock().unwrap().push(value)
+        });
+        spring.set_target(1.0);
+        clock.advance(Duration::from_millis(100));
+        assert!(!spring.is_at_rest());
+        let midway = spring.value();
+        assert!(midway > 0.0 && midway < 1.0);
+
+        // Retargeting keeps the current position and velocity.
+        let velocity = spring.velocity();
+        spring.set_target(-1.0);
+        assert_eq!(spring.value(), midway);
+        assert_eq!(spring.velocity(), velocity);
+
+        clock.advance(Duration::from_secs(5));
+        assert!(spring.is_at_rest());
+        assert_eq!(spring.value(), -1.0);
+        assert_eq!(values.lock().unwrap().last().copied(), Some(-1.0));
+        // A critically damped spring does not overshoot its target.
+        assert!(values.lock().unwrap().iter().all(|value| *value >= -1.0));
+    }
+
+    #[test]
+    fn test_spring_drop() {
+        let clock = MockClock::install();
+        let spring = Spring::with_setter(0.0, SpringConfig::critically_damped(200.0), |_: f64| {});
+        spring.set_target(1.0);
+        clock.advance(Duration::from_millis(100));
+        assert!(!spring.is_at_rest());
+        assert_eq!(clock.pending_tasks(), 1);
+
+        drop(spring);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.pending_tasks(), 0);
+        assert_eq!(clock.pending_timers(), 0);
+    }
+}
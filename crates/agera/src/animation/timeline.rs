@@ -0,0 +1,373 @@
+use crate::{common::*, display::*, file::File, geom::*, timer::Duration};
+use super::{Easing, Interpolate};
+
+/// A keyframed animation of named child display objects over
+/// a number of frames, played by a `MovieClip`.
+///
+/// Frames are zero based. Each `Track` targets a child by its Node path,
+/// resolved from the clip, and holds keyframes with the state of that child.
+/// A keyframe's state holds until the next keyframe, unless the keyframe
+/// has an easing, in which case the state is tweened towards the next keyframe.
+///
+/// # JSON
+///
+/// Timelines serialize to and from JSON, so that they can be
+/// authored outside the application and loaded through `Timeline::load_async()`:
+///
+/// ```json
+/// {
+///     "frame_rate": 24,
+///     "frames": 48,
+///     "labels": { "intro": 0, "loop": 24 },
+///     "tracks": [
+///         {
+///             "target": "ball",
+///             "keyframes": [
+///                 { "frame": 0, "position": [0, 0], "alpha": 0, "easing": "QuadOut" },
+///                 { "frame": 12, "position": [100, 0], "alpha": 1 },
+///                 { "frame": 24, "filters": [{ "type": "blur", "blur": [4, 4], "quality": 1 }] }
+///             ]
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Timeline {
+    #[serde(default = "default_frame_rate")]
+    frame_rate: f64,
+    frames: u32,
+    #[serde(default)]
+    labels: BTreeMap<String, u32>,
+    #[serde(default)]
+    tracks: Vec<Track>,
+}
+
+fn default_frame_rate() -> f64 {
+    24.0
+}
+
+impl Timeline {
+    /// Creates an empty timeline of `frames` frames, played
+    /// at `frame_rate` frames per second.
+    pub fn new(frame_rate: f64, frames: u32) -> Self {
+        assert!(frame_rate > 0.0, "Frame rate must be positive");
+        assert!(frames > 0, "Timeline must have at least one frame");
+        Self { frame_rate, frames, labels: BTreeMap::new(), tracks: vec![] }
+    }
+
+    /// Parses a timeline from JSON. Fails if a label or keyframe
+    /// is out of the frames of the timeline.
+    pub fn from_json(json: &str) -> Result<Self, json::Error> {
+        let custom = <json::Error as crate::util::ser::de::Error>::custom::<String>;
+        let mut timeline: Self = json::from_str(json)?;
+        if timeline.frame_rate.is_nan() || timeline.frame_rate <= 0.0 || timeline.frames == 0 {
+            return Err(custom("Timeline must have a positive frame rate and at least one frame".into()));
+        }
+        if let Some((label, frame)) = timeline.labels.iter().find(|(_, frame)| **frame >= timeline.frames) {
+            return Err(custom(format!("Label '{label}' is at frame {frame}, out of {} frames", timeline.frames)));
+        }
+        for track in &mut timeline.tracks {
+            if let Some(keyframe) = track.keyframes.iter().find(|keyframe| keyframe.frame >= timeline.frames) {
+                return Err(custom(format!("Keyframe of '{}' is at frame {}, out of {} frames", track.target, keyframe.frame, timeline.frames)));
+            }
+            track.keyframes.sort_by_key(|keyframe| keyframe.frame);
+            if let Some(keyframes) = track.keyframes.windows(2).find(|keyframes| keyframes[0].frame == keyframes[1].frame) {
+                return Err(custom(format!("Track '{}' has more than one keyframe at frame {}", track.target, keyframes[0].frame)));
+            }
+        }
+        Ok(timeline)
+    }
+
+    pub fn to_json(&self) -> String {
+        json::to_string(self).unwrap()
+    }
+
+    /// Loads a timeline from a JSON file, such as an `app:` file.
+    pub async fn load_async(url: &str) -> std::io::Result<Self> {
+        let json = File::new(url).read_utf8_async().await?;
+        Self::from_json(&json).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn frame_rate(&self) -> f64 {
+        self.frame_rate
+    }
+
+    /// The number of frames.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// The duration of a single frame.
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.frame_rate)
+    }
+
+    /// Labels a frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is out of the frames of the timeline.
+    pub fn label(mut self, label: &str, frame: u32) -> Self {
+        assert!(frame < self.frames, "Label frame out of bounds");
+        self.labels.insert(label.to_owned(), frame);
+        self
+    }
+
+    pub fn labels(&self) -> &BTreeMap<String, u32> {
+        &self.labels
+    }
+
+    /// The frame of a label.
+    pub fn label_frame(&self, label: &str) -> Option<u32> {
+        self.labels.get(label).copied()
+    }
+
+    /// The label of a frame, which is the label of the
+    /// closest labeled frame up to `frame`.
+    pub fn label_at(&self, frame: u32) -> Option<&str> {
+        self.labels.iter()
+            .filter(|(_, labeled)| **labeled <= frame)
+            .max_by_key(|(_, labeled)| **labeled)
+            .map(|(label, _)| label.as_str())
+    }
+
+    /// Resolves a frame or label into a frame.
+    pub fn resolve(&self, target: &FrameTarget) -> Option<u32> {
+        match target {
+            FrameTarget::Frame(frame) => Some(*frame).filter(|frame| *frame < self.frames),
+            FrameTarget::Label(label) => self.label_frame(label).filter(|frame| *frame < self.frames),
+        }
+    }
+
+    /// Adds a keyframe to the track of `target`, replacing
+    /// any keyframe at the same frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame of `keyframe` is out of the frames of the timeline.
+    pub fn keyframe(mut self, target: &str, keyframe: Keyframe) -> Self {
+        assert!(keyframe.frame < self.frames, "Keyframe frame out of bounds");
+        let track = match self.tracks.iter().position(|track| track.target == target) {
+            Some(i) => &mut self.tracks[i],
+            None => {
+                self.tracks.push(Track { target: target.to_owned(), keyframes: vec![] });
+                self.tracks.last_mut().unwrap()
+            },
+        };
+        match track.keyframes.binary_search_by_key(&keyframe.frame, |existing| existing.frame) {
+            Ok(i) => track.keyframes[i] = keyframe,
+            Err(i) => track.keyframes.insert(i, keyframe),
+        }
+        self
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Applies the state of every track at `frame` to the
+    /// children of `root`. Tracks whose target does not resolve
+    /// to a display object are ignored.
+    pub fn apply(&self, root: &DisplayObject, frame: u32) {
+        for track in &self.tracks {
+            let Some(target) = root.resolve_path(&track.target).and_then(|node| DisplayObject::try_from(node).ok()) else {
+                continue;
+            };
+            track.state_at(frame).apply(&target);
+        }
+    }
+}
+
+/// The keyframes of a child display object in a `Timeline`.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Track {
+    target: String,
+    #[serde(default)]
+    keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    /// The Node path of the child, relative to the clip.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The keyframes, ordered by frame.
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// The state of the child at `frame`, which is empty
+    /// before the first keyframe.
+    pub fn state_at(&self, frame: u32) -> ChildState {
+        let i = self.keyframes.partition_point(|keyframe| keyframe.frame <= frame);
+        let Some(keyframe) = i.checked_sub(1).map(|i| &self.keyframes[i]) else {
+            return ChildState::default();
+        };
+        match (keyframe.easing, self.keyframes.get(i)) {
+            (Some(easing), Some(next)) => {
+                let t = (frame - keyframe.frame) as f64 / (next.frame - keyframe.frame) as f64;
+                keyframe.state.interpolate(&next.state, easing.apply(t))
+            },
+            _ => keyframe.state.clone(),
+        }
+    }
+}
+
+/// A keyframe of a `Track`.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub frame: u32,
+    #[serde(flatten)]
+    pub state: ChildState,
+    /// If present, the state is tweened towards the next
+    /// keyframe following this easing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub easing: Option<Easing>,
+}
+
+impl Keyframe {
+    pub fn new(frame: u32, state: ChildState) -> Self {
+        Self { frame, state, easing: None }
+    }
+
+    /// Tweens the state towards the next keyframe.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = Some(easing);
+        self
+    }
+}
+
+/// The state of a child display object at a keyframe.
+/// Properties that are `None` are left unchanged.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChildState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Vector2d>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<Vector2d>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skew: Option<Vector2d>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_radians: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f64>,
+    /// Filters, which are not tweened.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filters: Option<Vec<BitmapFilter>>,
+}
+
+impl ChildState {
+    /// Interpolates the properties present in both states,
+    /// keeping the other properties of this state.
+    fn interpolate(&self, to: &Self, t: f64) -> Self {
+        fn lerp<T: Interpolate>(from: &Option<T>, to: &Option<T>, t: f64) -> Option<T> {
+            match (from, to) {
+                (Some(from), Some(to)) => Some(from.interpolate(to, t)),
+                _ => from.clone(),
+            }
+        }
+        Self {
+            position: lerp(&self.position, &to.position, t),
+            scale: lerp(&self.scale, &to.scale, t),
+            skew: lerp(&self.skew, &to.skew, t),
+            rotation_radians: lerp(&self.rotation_radians, &to.rotation_radians, t),
+            alpha: lerp(&self.alpha, &to.alpha, t),
+            filters: self.filters.clone(),
+        }
+    }
+
+    /// Assigns the present properties to a display object.
+    pub fn apply(&self, target: &DisplayObject) {
+        if let Some(position) = self.position {
+            target.set_position(position);
+        }
+        if let Some(scale) = self.scale {
+            target.set_scale(scale);
+        }
+        if let Some(skew) = self.skew {
+            target.set_skew(skew);
+        }
+        if let Some(rotation) = self.rotation_radians {
+            target.set_rotation_radians(rotation);
+        }
+        if let Some(alpha) = self.alpha {
+            target.set_alpha(alpha);
+        }
+        if let Some(filters) = &self.filters {
+            target.set_filters(filters.clone());
+        }
+    }
+}
+
+/// A frame of a `Timeline`, given either as a frame number or as a label.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FrameTarget {
+    Frame(u32),
+    Label(String),
+}
+
+impl From<u32> for FrameTarget {
+    fn from(value: u32) -> Self {
+        Self::Frame(value)
+    }
+}
+
+impl From<&str> for FrameTarget {
+    fn from(value: &str) -> Self {
+        Self::Label(value.to_owned())
+    }
+}
+
+impl From<String> for FrameTarget {
+    fn from(value: String) -> Self {
+        Self::Label(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{animation::*, geom::*};
+
+    #[test]
+    fn test_timeline() {
+        let timeline = Timeline::from_json(r#"{
+            "frame_rate": 10,
+            "frames": 20,
+            "labels": { "intro": 0, "loop": 10 },
+            "tracks": [{
+                "target": "ball",
+                "keyframes": [
+                    { "frame": 10, "alpha": 0.5 },
+                    { "frame": 0, "position": [0, 0], "alpha": 0, "easing": "Linear" }
+                ]
+            }]
+        }"#).unwrap();
+        assert_eq!(timeline.frame_duration(), crate::timer::Duration::from_millis(100));
+        assert_eq!(timeline.label_at(14), Some("loop"));
+        assert_eq!(timeline.resolve(&"loop".into()), Some(10));
+        assert_eq!(timeline.resolve(&20.into()), None);
+
+        let track = &timeline.tracks()[0];
+        let state = track.state_at(5);
+        assert_eq!(state.position, Some(Vector2d(0.0, 0.0)));
+        assert_eq!(state.alpha, Some(0.25));
+        assert_eq!(track.state_at(15).alpha, Some(0.5));
+        assert_eq!(Timeline::from_json(&timeline.to_json()).unwrap(), timeline);
+
+        assert!(Timeline::from_json(r#"{ "frames": 20, "labels": { "end": 20 } }"#).is_err());
+        assert!(Timeline::from_json(r#"{ "frames": 20, "tracks": [{ "target": "ball", "keyframes": [{ "frame": 20 }] }] }"#).is_err());
+        assert!(Timeline::from_json(r#"{ "frames": 20, "tracks": [{ "target": "ball", "keyframes": [{ "frame": 1 }, { "frame": 1 }] }] }"#).is_err());
+    }
+
+    #[test]
+    fn test_timeline_colors() {
+        let color = crate::util::Color::new(0.1, 0.2, 0.3, 0.123_456_79);
+        let timeline = Timeline::new(24.0, 2).keyframe("ball", Keyframe::new(1, ChildState {
+            filters: Some(vec![crate::display::BitmapFilter::DropShadow { color, offset: Vector2d(2.0, 2.0), spread: 0, blur: Vector2d(4.0, 4.0) }]),
+            ..Default::default()
+        }));
+        assert_eq!(Timeline::from_json(&timeline.to_json()).unwrap(), timeline);
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use crate::{binding::Property, timer::Duration};
+use super::{Animation, Easing, Interpolate};
+
+/// An animation of a value from a start value to an end value
+/// over a duration, following an easing function.
+/// 
+/// Tweens usually animate an observable property of a display object:
+/// 
+/// ```ignore
+/// Tween::new(&display_object.alpha_property(), 0.0, Duration::from_millis(300))
+///     .easing(Easing::QuadOut)
+///     .play();
+/// ```
+pub struct Tween<T: Interpolate> {
+    from: Mutex<Option<T>>,
+    to: T,
+    duration: Duration,
+    easing: Easing,
+    get: Option<Box<dyn Fn() -> Option<T> + Send + Sync>>,
+    set: Box<dyn Fn(T) + Send + Sync>,
+}
+
+impl<T: Interpolate> Tween<T> {
+    /// Creates a tween of a property to `to`. Unless a start value
+    /// is given through `from()`, the tween starts from the value
+    /// of the property when the tween is first sought.
+    pub fn new(property: &Property<T>, to: T, duration: Duration) -> Self {
+        let (getter, setter) = (property.clone(), property.clone());
+        Self {
+            from: Mutex::new(None),
+            to,
+            duration,
+            easing: Easing::Linear,
+            get: Some(Box::new(move || getter.get())),
+            set: Box::new(move |value| setter.set(value)),
+        }
+    }
+
+    /// Creates a tween passing values from `from` to `to` to a function.
+    pub fn with_setter(from: T, to: T, duration: Duration, setter: impl Fn(T) + Send + Sync + 'static) -> Self {
+        Self {
+            from: Mutex::new(Some(from)),
+            to,
+            duration,
+            easing: Easing::Linear,
+            get: None,
+            set: Box::new(setter),
+        }
+    }
+
+    /// Sets the start value.
+    pub fn from(self, value: T) -> Self {
+        *self.from.lock().unwrap_or_else(PoisonError::into_inner) = Some(value);
+        self
+    }
+
+    /// Sets the easing function, which defaults to `Easing::Linear`.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+impl<T: Interpolate> Animation for Tween<T> {
+    fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn seek(&self, time: Duration) {
+        let from = {
+            let mut from = self.from.lock().unwrap_or_else(PoisonError::into_inner);
+            if from.is_none() {
+                *from = self.get.as_ref().and_then(|get| get());
+            }
+            match from.as_ref() {
+                Some(from) => from.clone(),
+                None => return,
+            }
+        };
+        let progress = if self.duration.is_zero() { 1.0 } else { time.as_secs_f64() / self.duration.as_secs_f64() };
+        (self.set)(from.interpolate(&self.to, self.easing.apply(progress)));
+    }
+}
+
+/// An animation that does nothing for a duration, used
+/// for delays within sequences.
+pub struct Delay(pub Duration);
+
+impl Animation for Delay {
+    fn duration(&self) -> Duration {
+        self.0
+    }
+
+    fn seek(&self, _time: Duration) {
+    }
+}
+
+/// An animation calling a function when reached, used
+/// for callbacks within sequences.
+pub struct Call(pub Arc<dyn Fn() + Send + Sync>);
+
+impl Animation for Call {
+    fn duration(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn seek(&self, _time: Duration) {
+        (self.0)();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, pin::pin, task::{Context, Poll}};
+    use futures::task::noop_waker;
+    use crate::{animation::*, display::*, geom::*, timer::{testing::MockClock, Duration}};
+
+    #[test]
+    fn test_tween() {
+        let clock = MockClock::install();
+        let display_object = DisplayObject::new();
+        let tween = Tween::new(&display_object.alpha_property(), 0.0, Duration::from_millis(100))
+            .then(Tween::new(&display_object.position_property(), Vector2d(10.0, 20.0), Duration::from_millis(100)).easing(Easing::QuadOut))
+            .play();
+        clock.advance(Duration::from_millis(50));
+        let progress = tween.elapsed().as_secs_f64() / 0.1;
+        assert!(progress > 0.0 && progress <= 0.5);
+        assert!((display_object.alpha() - (1.0 - progress)).abs() < 1e-9);
+        clock.advance(Duration::from_secs(1));
+        assert!(tween.is_finished());
+        assert_eq!(display_object.alpha(), 0.0);
+        assert_eq!(display_object.position(), Vector2d(10.0, 20.0));
+
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(pin!(tween.finished()).poll(&mut cx), Poll::Ready(true));
+
+        // Yoyo repeats alternate direction, ending at the start value.
+        let values = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let yoyo = Tween::with_setter(0.0, 1.0, Duration::from_millis(100), {
+            let values = values.clone();
+            move |value: f64| values.lock().unwrap().push(value)
+        }).repeat(2).yoyo();
+        assert_eq!(yoyo.duration(), Duration::from_millis(200));
+        for time in [50, 100, 150, 200] {
+            yoyo.seek(Duration::from_millis(time));
+        }
+        assert_eq!(*values.lock().unwrap(), [0.5, 1.0, 0.5, 0.0]);
+    }
+}
@@ -38,4 +38,13 @@ mod bootstrap;
 pub use bootstrap::*;
 
 mod window;
-pub use window::*;
\ No newline at end of file
+pub use window::*;
+
+mod error;
+pub use error::*;
+
+mod frame_stats;
+pub use frame_stats::*;
+
+mod frame_loop;
+pub use frame_loop::*;
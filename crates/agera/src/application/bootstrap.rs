@@ -24,6 +24,9 @@ pub macro start {
             // Setup file directories
             ::agera::file::__agera_File_bootstrap().await;
 
+            // Start the frame loop
+            ::agera::application::__agera_frame_loop_bootstrap();
+
             // Start
             $start_action.await;
         }
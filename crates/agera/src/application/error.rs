@@ -0,0 +1,55 @@
+use std::{cell::Cell, fmt::{Display, Formatter}};
+use crate::common::*;
+
+/// An error reported through `application::on_error()` instead of
+/// bringing down the application.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ApplicationError {
+    /// An event listener panicked. The remaining listeners of the
+    /// emission were still invoked.
+    ListenerPanic {
+        message: String,
+    },
+    /// An event was not emitted because emissions were nested
+    /// `depth` levels deep, which usually indicates listeners
+    /// emitting each other's events in a cycle.
+    EmitRecursionLimit {
+        depth: usize,
+    },
+}
+
+impl Display for ApplicationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ListenerPanic { message } => write!(f, "Event listener panicked: {message}"),
+            Self::EmitRecursionLimit { depth } => write!(f, "Event emission exceeded the recursion limit of {depth}"),
+        }
+    }
+}
+
+impl std::error::Error for ApplicationError {}
+
+static ON_ERROR: Lazy<EventEmitter<ApplicationError>> = Lazy::new(EventEmitter::new);
+
+/// Emits errors the application recovered from, such as panicking
+/// event listeners. Errors are printed to the standard error
+/// when this event emitter has no listeners.
+pub fn on_error() -> &'static EventEmitter<ApplicationError> {
+    &ON_ERROR
+}
+
+thread_local! {
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+pub(crate) fn report_error(error: ApplicationError) {
+    // Errors raised by the error listeners themselves are not
+    // reported back to them.
+    if ON_ERROR.listener_count() == 0 || REPORTING.with(|reporting| reporting.get()) {
+        eprintln!("{error}");
+        return;
+    }
+    REPORTING.with(|reporting| reporting.set(true));
+    ON_ERROR.emit_unlimited(error);
+    REPORTING.with(|reporting| reporting.set(false));
+}
@@ -0,0 +1,97 @@
+use crate::{application::{frame_stats, FramePhase, FrameStats}, common::*, events, timer::{self, Duration}};
+
+const PHASES: [FramePhase; 3] = [FramePhase::Events, FramePhase::Layout, FramePhase::Render];
+
+/// The frame loop of the application, emitting in each phase of a frame.
+struct FrameLoop {
+    phases: [EventEmitter<Duration>; 3],
+}
+
+static FRAME_LOOP: Lazy<FrameLoop> = Lazy::new(FrameLoop::new);
+
+/// Returns an event emitter that emits during a phase of each frame of the
+/// application's frame loop, with the time elapsed since the previous frame.
+///
+/// The frame loop runs once per frame budget, emitting in the `Events`,
+/// `Layout` and `Render` phases in that order. The time spent by the listeners
+/// of each phase is recorded into [`frame_stats()`], which counts a frame
+/// for each run of the frame loop. Platform renderers draw the display list
+/// during the `Render` phase. Nodes that left their tree are notified through
+/// `on_removed_from_tree()` at the start of the `Events` phase.
+///
+/// ```ignore
+/// application::on_frame_phase(FramePhase::Events).listener(|delta| {
+///     // Per-frame logic
+/// });
+/// ```
+pub fn on_frame_phase(phase: FramePhase) -> &'static EventEmitter<Duration> {
+    &FRAME_LOOP.phases[phase.index()]
+}
+
+impl FrameLoop {
+    fn new() -> Self {
+        Self {
+            phases: [EventEmitter::new(), EventEmitter::new(), EventEmitter::new()],
+        }
+    }
+
+    /// Runs the phases of a frame, recording the frame into `stats`.
+    fn run_frame(&self, stats: &FrameStats, delta: Duration) {
+        let mut frame = stats.begin_frame();
+        for phase in PHASES {
+            frame.measure(phase, || {
+                if phase == FramePhase::Events {
+                    events::check_removed_from_tree();
+                }
+                self.phases[phase.index()].emit(delta);
+            });
+        }
+    }
+}
+
+/// *Internal function used by `application::start!`.*
+#[doc(hidden)]
+pub fn __agera_frame_loop_bootstrap() {
+    future::exec(async {
+        let stats = frame_stats();
+        let mut ticker = timer::animation_ticker(stats.frame_budget());
+        loop {
+            let delta = ticker.tick().await;
+            FRAME_LOOP.run_frame(stats, delta);
+            let budget = stats.frame_budget();
+            if ticker.period() != budget {
+                ticker.set_period(budget);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use crate::{application::*, timer::Duration};
+    use super::FrameLoop;
+
+    #[test]
+    fn test_frame_loop() {
+        let stats = FrameStats::new();
+        let frame_loop = FrameLoop::new();
+        let order = Arc::new(RwLock::new(vec![]));
+        for (phase, time) in [(FramePhase::Render, 4), (FramePhase::Events, 1), (FramePhase::Layout, 2)] {
+            let order = Arc::clone(&order);
+            frame_loop.phases[phase.index()].listener(move |_| {
+                order.write().unwrap().push(phase);
+                std::thread::sleep(Duration::from_millis(time));
+            });
+        }
+
+        frame_loop.run_frame(&stats, Duration::ZERO);
+        frame_loop.run_frame(&stats, Duration::from_millis(17));
+        assert_eq!(*order.read().unwrap(), [FramePhase::Events, FramePhase::Layout, FramePhase::Render].repeat(2));
+        assert_eq!(stats.frame_count(), 2);
+        assert!(stats.average_phase_time(FramePhase::Events) >= Duration::from_millis(1));
+        assert!(stats.average_phase_time(FramePhase::Layout) >= Duration::from_millis(2));
+        assert!(stats.average_phase_time(FramePhase::Render) >= Duration::from_millis(4));
+        assert!(stats.recent_frames()[1].interval >= Duration::from_millis(7));
+    }
+}
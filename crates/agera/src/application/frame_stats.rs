@@ -0,0 +1,281 @@
+use std::{collections::VecDeque, sync::{Mutex, MutexGuard, PoisonError}};
+use crate::{common::*, timer::{Duration, Instant}};
+
+/// Number of frames kept for statistics.
+pub const FRAME_HISTORY_LENGTH: usize = 240;
+
+/// A phase of a frame.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum FramePhase {
+    /// Dispatch of input and timer events.
+    Events,
+    Layout,
+    Render,
+}
+
+impl FramePhase {
+    pub(super) fn index(&self) -> usize {
+        match self {
+            Self::Events => 0,
+            Self::Layout => 1,
+            Self::Render => 2,
+        }
+    }
+}
+
+/// Timing of a single frame, emitted by `FrameStats::on_frame()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FrameTiming {
+    /// The time since the start of the previous frame,
+    /// or zero for the first frame.
+    pub interval: Duration,
+    /// The number of frames missed before this frame,
+    /// according to the frame budget.
+    pub dropped: u32,
+    phases: [Duration; 3],
+}
+
+impl FrameTiming {
+    /// The time spent in a phase of the frame.
+    pub fn phase(&self, phase: FramePhase) -> Duration {
+        self.phases[phase.index()]
+    }
+
+    /// The time spent in all phases of the frame.
+    pub fn work(&self) -> Duration {
+        self.phases.iter().sum()
+    }
+}
+
+/// A histogram of frame intervals, returned by `FrameStats::histogram()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FrameTimeHistogram {
+    /// Buckets in increasing order.
+    pub buckets: Vec<HistogramBucket>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HistogramBucket {
+    /// The inclusive upper bound of the bucket, or `None` for the last bucket.
+    pub upper_bound: Option<Duration>,
+    pub count: usize,
+}
+
+/// Frame-timing statistics of the application, returned by `application::frame_stats()`.
+/// 
+/// Frames are recorded by the application's frame loop through `begin_frame()`,
+/// which also measures the time spent in each `FramePhase`. See
+/// [`on_frame_phase()`](crate::application::on_frame_phase).
+/// 
+/// ```ignore
+/// let stats = application::frame_stats();
+/// println!("{:.1} FPS, {} dropped frames", stats.fps(), stats.dropped_frames());
+/// ```
+pub struct FrameStats {
+    state: Mutex<StatsState>,
+    on_frame: EventEmitter<FrameTiming>,
+}
+
+struct StatsState {
+    budget: Duration,
+    history: VecDeque<FrameTiming>,
+    last_frame_start: Option<Instant>,
+    frame_count: u64,
+    dropped_frames: u64,
+}
+
+static FRAME_STATS: Lazy<FrameStats> = Lazy::new(FrameStats::new);
+
+/// Returns the frame-timing statistics of the application.
+pub fn frame_stats() -> &'static FrameStats {
+    &FRAME_STATS
+}
+
+impl FrameStats {
+    pub(super) fn new() -> Self {
+        Self {
+            state: Mutex::new(StatsState {
+                budget: Duration::from_nanos(1_000_000_000 / 60),
+                history: VecDeque::with_capacity(FRAME_HISTORY_LENGTH),
+                last_frame_start: None,
+                frame_count: 0,
+                dropped_frames: 0,
+            }),
+            on_frame: EventEmitter::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, StatsState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Emits the timing of each recorded frame.
+    pub fn on_frame(&self) -> &EventEmitter<FrameTiming> {
+        &self.on_frame
+    }
+
+    /// The time budget of a frame, which defaults to a sixtieth of a second.
+    pub fn frame_budget(&self) -> Duration {
+        self.state().budget
+    }
+
+    pub fn set_frame_budget(&self, budget: Duration) {
+        assert!(!budget.is_zero(), "Frame budget must be non-zero");
+        self.state().budget = budget;
+    }
+
+    /// Starts recording a frame, which is recorded when the returned
+    /// `Frame` is dropped.
+    pub fn begin_frame(&self) -> Frame<'_> {
+        Frame { stats: self, start: Instant::now(), phases: [Duration::ZERO; 3] }
+    }
+
+    fn record(&self, start: Instant, phases: [Duration; 3]) {
+        let timing = {
+            let mut state = self.state();
+            let interval = state.last_frame_start.map(|last| start.since(last)).unwrap_or(Duration::ZERO);
+            let budget = state.budget;
+            let dropped = if interval.as_secs_f64() > budget.as_secs_f64() * 1.5 {
+                (interval.as_secs_f64() / budget.as_secs_f64()).round() as u32 - 1
+            } else {
+                0
+            };
+            let timing = FrameTiming { interval, dropped, phases };
+            state.last_frame_start = Some(start);
+            state.frame_count += 1;
+            state.dropped_frames += dropped as u64;
+            if state.history.len() == FRAME_HISTORY_LENGTH {
+                state.history.pop_front();
+            }
+            state.history.push_back(timing.clone());
+            timing
+        };
+        self.on_frame.emit(timing);
+    }
+
+    /// The number of recorded frames.
+    pub fn frame_count(&self) -> u64 {
+        self.state().frame_count
+    }
+
+    /// The total number of dropped frames.
+    pub fn dropped_frames(&self) -> u64 {
+        self.state().dropped_frames
+    }
+
+    /// The frames per second over the last second of recorded frames.
+    pub fn fps(&self) -> f64 {
+        let state = self.state();
+        let (mut frames, mut total) = (0, Duration::ZERO);
+        for timing in state.history.iter().rev().filter(|timing| !timing.interval.is_zero()) {
+            if total >= Duration::from_secs(1) {
+                break;
+            }
+            frames += 1;
+            total += timing.interval;
+        }
+        if total.is_zero() { 0.0 } else { frames as f64 / total.as_secs_f64() }
+    }
+
+    /// The average interval between recent frames.
+    pub fn average_frame_time(&self) -> Duration {
+        average(self.state().history.iter().map(|timing| timing.interval).filter(|interval| !interval.is_zero()))
+    }
+
+    /// The average time spent in a phase over recent frames.
+    pub fn average_phase_time(&self, phase: FramePhase) -> Duration {
+        average(self.state().history.iter().map(|timing| timing.phase(phase)))
+    }
+
+    /// The timings of recent frames, from the oldest to the latest,
+    /// up to `FRAME_HISTORY_LENGTH` frames.
+    pub fn recent_frames(&self) -> Vec<FrameTiming> {
+        self.state().history.iter().cloned().collect()
+    }
+
+    /// A histogram of recent frame intervals, with buckets bounded by
+    /// half, one, two, three and six times the frame budget.
+    pub fn histogram(&self) -> FrameTimeHistogram {
+        let state = self.state();
+        let mut buckets: Vec<HistogramBucket> = [0.5, 1.0, 2.0, 3.0, 6.0].iter()
+            .map(|factor| HistogramBucket { upper_bound: Some(state.budget.mul_f64(*factor)), count: 0 })
+            .chain([HistogramBucket { upper_bound: None, count: 0 }])
+            .collect();
+        for timing in state.history.iter().filter(|timing| !timing.interval.is_zero()) {
+            let bucket = buckets.iter_mut()
+                .find(|bucket| bucket.upper_bound.is_none_or(|bound| timing.interval <= bound))
+                .unwrap();
+            bucket.count += 1;
+        }
+        FrameTimeHistogram { buckets }
+    }
+
+    /// Clears the statistics.
+    pub fn reset(&self) {
+        let mut state = self.state();
+        state.history.clear();
+        state.last_frame_start = None;
+        state.frame_count = 0;
+        state.dropped_frames = 0;
+    }
+}
+
+fn average(durations: impl Iterator<Item = Duration>) -> Duration {
+    let (count, total) = durations.fold((0u32, Duration::ZERO), |(count, total), duration| (count + 1, total + duration));
+    if count == 0 { Duration::ZERO } else { total / count }
+}
+
+/// A frame being recorded, returned by `FrameStats::begin_frame()`.
+pub struct Frame<'a> {
+    stats: &'a FrameStats,
+    start: Instant,
+    phases: [Duration; 3],
+}
+
+impl Frame<'_> {
+    /// Runs a phase of the frame, adding the time it takes to the phase.
+    pub fn measure<R>(&mut self, phase: FramePhase, function: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let result = function();
+        self.add_phase_time(phase, Instant::now().since(start));
+        result
+    }
+
+    pub fn add_phase_time(&mut self, phase: FramePhase, duration: Duration) {
+        self.phases[phase.index()] += duration;
+    }
+}
+
+impl Drop for Frame<'_> {
+    fn drop(&mut self) {
+        self.stats.record(self.start, self.phases);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{application::*, timer::{testing::MockClock, Duration}};
+
+    #[test]
+    fn test_frame_stats() {
+        let clock = MockClock::install();
+        let stats = FrameStats::new();
+        for interval in [0, 16, 17, 16, 50, 17] {
+            clock.advance(Duration::from_millis(interval));
+            let mut frame = stats.begin_frame();
+            frame.measure(FramePhase::Layout, || clock.advance(Duration::from_millis(2)));
+            frame.add_phase_time(FramePhase::Render, Duration::from_millis(4));
+        }
+        assert_eq!(stats.frame_count(), 6);
+        assert_eq!(stats.dropped_frames(), 2);
+        assert_eq!(stats.average_phase_time(FramePhase::Layout), Duration::from_millis(2));
+        assert_eq!(stats.recent_frames()[5].work(), Duration::from_millis(6));
+        let histogram = stats.histogram();
+        assert_eq!(histogram.buckets.iter().map(|bucket| bucket.count).collect::<Vec<_>>(), [0, 0, 4, 0, 1, 0]);
+        assert!(stats.fps() > 30.0 && stats.fps() < 60.0);
+
+        stats.reset();
+        assert_eq!(stats.frame_count(), 0);
+        assert!(stats.recent_frames().is_empty());
+    }
+}
@@ -0,0 +1,461 @@
+/*!
+Reactive data binding between model values and display properties.
+
+An `Observable` is a cell holding a value that emits when the value changes.
+A computed observable, created through `computed()`, derives its value from
+other observables and updates whenever any observable it read changes.
+
+```ignore
+use agera::binding::*;
+
+let first_name = Observable::new("Ada".to_owned());
+let last_name = Observable::new("Lovelace".to_owned());
+let full_name = computed({
+    let (first_name, last_name) = (first_name.clone(), last_name.clone());
+    move || format!("{} {}", first_name.get(), last_name.get())
+});
+```
+
+Observables are bound to observable properties of display objects
+through `bind()` and `bind_two_way()`:
+
+```ignore
+bind(&full_name, &label.html_property());
+bind_two_way(&volume, &slider.value_property());
+```
+
+A binding is disposed by calling `Binding::dispose()`. It is also disposed
+automatically once its target display object leaves its tree, either by
+itself or along with an ancestor, which is detected once per frame and whenever
+the binding propagates a value, and once the target is found dropped when
+the binding propagates a value. Moving the target within its tree keeps
+the binding.
+*/
+
+use std::{cell::RefCell, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, RwLock, Weak}};
+use crate::{common::*, events::PropertyChange, util::inheritance::{Node, WeakNodeRef}};
+
+thread_local! {
+    /// Dependencies read by the computed observables being evaluated,
+    /// from the outermost to the innermost.
+    static TRACKING: RefCell<Vec<Vec<Arc<dyn Dependency>>>> = RefCell::new(vec![]);
+}
+
+/// An observable read while evaluating a computed observable.
+trait Dependency: Send + Sync {
+    fn changed(&self) -> &EventEmitter<()>;
+}
+
+/// A cell holding a value that emits when the value changes.
+/// 
+/// Cloning an `Observable` clones a reference to the same cell.
+pub struct Observable<T: Clone + PartialEq + Send + Sync + 'static> {
+    inner: Arc<ObservableInner<T>>,
+}
+
+struct ObservableInner<T: Clone> {
+    value: RwLock<T>,
+    on_change: EventEmitter<T>,
+    changed: EventEmitter<()>,
+    /// Listeners to the dependencies of a computed observable.
+    dependencies: Mutex<DependencyListeners>,
+}
+
+impl<T: Clone + Send + Sync> Dependency for ObservableInner<T> {
+    fn changed(&self) -> &EventEmitter<()> {
+        &self.changed
+    }
+}
+
+/// Removes listeners to dependencies when replaced or dropped.
+#[derive(Default)]
+struct DependencyListeners(Vec<EventListener<()>>);
+
+impl Drop for DependencyListeners {
+    fn drop(&mut self) {
+        for listener in self.0.iter() {
+            listener.remove();
+        }
+    }
+}
+
+impl<T: Clone + PartialEq + Send + Sync + 'static> Clone for Observable<T> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<T: Clone + PartialEq + Send + Sync + 'static> Observable<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Arc::new(ObservableInner {
+                value: RwLock::new(value),
+                on_change: EventEmitter::new(),
+                changed: EventEmitter::new(),
+                dependencies: Mutex::new(default()),
+            }),
+        }
+    }
+
+    /// Returns the current value. When called while evaluating
+    /// a computed observable, the computed observable starts
+    /// depending on this observable.
+    pub fn get(&self) -> T {
+        TRACKING.with(|tracking| {
+            if let Some(dependencies) = tracking.borrow_mut().last_mut() {
+                let dependency: Arc<dyn Dependency> = self.inner.clone();
+                if !dependencies.iter().any(|other| Arc::ptr_eq(other, &dependency)) {
+                    dependencies.push(dependency);
+                }
+            }
+        });
+        self.get_untracked()
+    }
+
+    /// Returns the current value without becoming a dependency
+    /// of a computed observable.
+    pub fn get_untracked(&self) -> T {
+        self.inner.value.read().unwrap().clone()
+    }
+
+    /// Sets the value, emitting `on_change()` if it differs from the current value.
+    pub fn set(&self, value: T) {
+        {
+            let mut current = self.inner.value.write().unwrap();
+            if *current == value {
+                return;
+            }
+            *current = value.clone();
+        }
+        self.inner.on_change.emit(value);
+        self.inner.changed.emit(());
+    }
+
+    /// Sets the value from the result of `function` applied to the current value.
+    pub fn update(&self, function: impl FnOnce(T) -> T) {
+        self.set(function(self.get_untracked()));
+    }
+
+    /// Emits the new value whenever the value changes.
+    pub fn on_change(&self) -> &EventEmitter<T> {
+        &self.inner.on_change
+    }
+}
+
+/// Evaluates `function`, returning its result and the observables it read.
+fn track<T>(function: &dyn Fn() -> T) -> (T, Vec<Arc<dyn Dependency>>) {
+    TRACKING.with(|tracking| tracking.borrow_mut().push(vec![]));
+    let value = function();
+    let dependencies = TRACKING.with(|tracking| tracking.borrow_mut().pop().unwrap());
+    (value, dependencies)
+}
+
+/// Creates an observable whose value is the result of `function`, evaluated
+/// again whenever any observable it read through `Observable::get()` changes.
+/// The set of dependencies is collected again on each evaluation.
+pub fn computed<T, F>(function: F) -> Observable<T>
+    where
+        T: Clone + PartialEq + Send + Sync + 'static,
+        F: Fn() -> T + Send + Sync + 'static
+{
+    let function: Arc<dyn Fn() -> T + Send + Sync> = Arc::new(function);
+    let (value, dependencies) = track(&*function);
+    let observable = Observable::new(value);
+    subscribe_dependencies(Arc::downgrade(&observable.inner), function, dependencies);
+    observable
+}
+
+fn subscribe_dependencies<T>(observable: Weak<ObservableInner<T>>, function: Arc<dyn Fn() -> T + Send + Sync>, dependencies: Vec<Arc<dyn Dependency>>)
+    where T: Clone + PartialEq + Send + Sync + 'static
+{
+    let Some(inner) = observable.upgrade() else {
+        return;
+    };
+    let listeners = dependencies.iter().map(|dependency| {
+        let (observable, function) = (observable.clone(), Arc::clone(&function));
+        dependency.changed().listener(move |_| {
+            let Some(inner) = observable.upgrade() else {
+                return;
+            };
+            let (value, dependencies) = track(&*function);
+            subscribe_dependencies(Arc::downgrade(&inner), Arc::clone(&function), dependencies);
+            Observable { inner }.set(value);
+        })
+    }).collect();
+    // Replacing the listeners removes the previous ones.
+    *inner.dependencies.lock().unwrap() = DependencyListeners(listeners);
+}
+
+/// An observable property of an object in the node graph, such as the
+/// `alpha` property of a display object. Properties are created by the
+/// `<name>_property()` methods generated through `observable_properties!`.
+/// 
+/// A property holds a weak reference to its owner.
+pub struct Property<T: Clone + Send + Sync + 'static> {
+    owner: WeakNodeRef,
+    get: Arc<dyn Fn(Node) -> Option<T> + Send + Sync>,
+    set: Arc<dyn Fn(Node, T) + Send + Sync>,
+    on_change: PropertyOnChange<T>,
+}
+
+/// Returns the change event emitter of a property for its owner,
+/// if the owner is of the expected type.
+type PropertyOnChange<T> = Arc<dyn Fn(Node) -> Option<Arc<EventEmitter<PropertyChange<T>>>> + Send + Sync>;
+
+impl<T: Clone + Send + Sync + 'static> Clone for Property<T> {
+    fn clone(&self) -> Self {
+        Self {
+            owner: self.owner.clone(),
+            get: Arc::clone(&self.get),
+            set: Arc::clone(&self.set),
+            on_change: Arc::clone(&self.on_change),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Property<T> {
+    pub fn new<O>(owner: &O, getter: fn(&O) -> T, setter: fn(&O, T) -> O, on_change: fn(&O) -> Arc<EventEmitter<PropertyChange<T>>>) -> Self
+        where O: AsRef<Node> + TryFrom<Node> + 'static
+    {
+        Self {
+            owner: owner.as_ref().downgrade_ref(),
+            get: Arc::new(move |node| O::try_from(node).ok().map(|owner| getter(&owner))),
+            set: Arc::new(move |node, value| {
+                if let Ok(owner) = O::try_from(node) {
+                    setter(&owner, value);
+                }
+            }),
+            on_change: Arc::new(move |node| O::try_from(node).ok().map(|owner| on_change(&owner))),
+        }
+    }
+
+    /// The owner of the property, if it is still alive.
+    pub fn owner(&self) -> Option<Node> {
+        self.owner.upgrade()
+    }
+
+    /// Returns the value of the property, if its owner is still alive.
+    pub fn get(&self) -> Option<T> {
+        self.owner().and_then(|owner| (self.get)(owner))
+    }
+
+    /// Sets the value of the property, if its owner is still alive.
+    pub fn set(&self, value: T) {
+        if let Some(owner) = self.owner() {
+            (self.set)(owner, value);
+        }
+    }
+
+    fn on_change(&self) -> Option<Arc<EventEmitter<PropertyChange<T>>>> {
+        self.owner().and_then(|owner| (self.on_change)(owner))
+    }
+}
+
+/// A binding between an observable and a property, returned
+/// by `bind()` and `bind_two_way()`.
+#[derive(Clone)]
+pub struct Binding {
+    inner: Arc<BindingInner>,
+}
+
+struct BindingInner {
+    disposed: AtomicBool,
+    /// Functions removing the listeners of the binding.
+    removers: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+}
+
+impl Binding {
+    fn new() -> Self {
+        Self {
+            inner: Arc::new(BindingInner {
+                disposed: AtomicBool::new(false),
+                removers: Mutex::new(vec![]),
+            }),
+        }
+    }
+
+    fn add_listener<T: Clone + Send + Sync + 'static>(&self, listener: EventListener<T>) {
+        self.inner.removers.lock().unwrap().push(Box::new(move || {
+            listener.remove();
+        }));
+    }
+
+    /// Stops propagating values.
+    pub fn dispose(&self) {
+        self.inner.disposed.store(true, Ordering::SeqCst);
+        let removers = std::mem::take(&mut *self.inner.removers.lock().unwrap());
+        for remover in removers {
+            remover();
+        }
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.inner.disposed.load(Ordering::SeqCst)
+    }
+
+    /// Disposes the binding once the owner of the property
+    /// is removed from the tree.
+    fn dispose_on_removal<T: Clone + Send + Sync + 'static>(&self, property: &Property<T>) {
+        if let Some(owner) = property.owner() {
+            let listener = crate::events::on_removed_from_tree(&owner).listener({
+                let binding = self.clone();
+                move |_| binding.dispose()
+            });
+            self.add_listener(listener);
+        }
+    }
+
+    /// Returns whether the binding may propagate a value to or from the
+    /// property, disposing the binding if its owner is gone or has left
+    /// the tree since the last frame.
+    fn check_owner<T: Clone + Send + Sync + 'static>(&self, property: &Property<T>) -> bool {
+        if self.is_disposed() {
+            return false;
+        }
+        let Some(owner) = property.owner() else {
+            self.dispose();
+            return false;
+        };
+        crate::events::check_node_removed_from_tree(&owner);
+        !self.is_disposed()
+    }
+}
+
+/// Binds a property to an observable, assigning the value of the
+/// observable to the property now and whenever it changes.
+pub fn bind<T>(source: &Observable<T>, target: &Property<T>) -> Binding
+    where T: Clone + PartialEq + Send + Sync + 'static
+{
+    let binding = Binding::new();
+    binding.dispose_on_removal(target);
+    target.set(source.get_untracked());
+    let listener = source.on_change().listener({
+        let (binding, target) = (binding.clone(), target.clone());
+        move |value| {
+            if binding.check_owner(&target) {
+                target.set(value);
+            }
+        }
+    });
+    binding.add_listener(listener);
+    binding
+}
+
+/// Binds a property to an observable in both directions. The property
+/// is assigned the value of the observable now, and afterwards changes
+/// to either are assigned to the other.
+pub fn bind_two_way<T>(source: &Observable<T>, target: &Property<T>) -> Binding
+    where T: Clone + PartialEq + Send + Sync + 'static
+{
+    let binding = bind(source, target);
+    if let Some(on_change) = target.on_change() {
+        let listener = on_change.listener({
+            let (binding, source, target) = (binding.clone(), source.clone(), target.clone());
+            move |change| {
+                if binding.check_owner(&target) {
+                    source.set(change.new_value);
+                }
+            }
+        });
+        binding.add_listener(listener);
+    }
+    binding
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{binding::*, display::*, util::inheritance::Node};
+
+    #[test]
+    fn test_computed() {
+        let a = Observable::new(1);
+        let b = Observable::new(2);
+        let use_b = Observable::new(true);
+        let sum = computed({
+            let (a, b, use_b) = (a.clone(), b.clone(), use_b.clone());
+            move || a.get() + if use_b.get() { b.get() } else { 0 }
+        });
+        assert_eq!(sum.get(), 3);
+        a.set(10);
+        assert_eq!(sum.get(), 12);
+        use_b.set(false);
+        assert_eq!(sum.get(), 10);
+        // `b` is no longer a dependency.
+        b.set(100);
+        assert_eq!(sum.get(), 10);
+    }
+
+    #[test]
+    fn test_bind() {
+        let root = DisplayObject::new();
+        let display_object = DisplayObject::new();
+        let alpha = Observable::new(0.5);
+
+        let binding = bind_two_way(&alpha, &display_object.alpha_property());
+        assert_eq!(display_object.alpha(), 0.5);
+        alpha.set(0.25);
+        assert_eq!(display_object.alpha(), 0.25);
+        display_object.set_alpha(0.75);
+        assert_eq!(alpha.get(), 0.75);
+
+        // Removing the target from the tree disposes the binding.
+        root.add_child(&display_object);
+        alpha.set(0.1);
+        assert_eq!(display_object.alpha(), 0.1);
+        display_object.remove_from_parent();
+        crate::events::check_removed_from_tree();
+        assert!(binding.is_disposed());
+        assert_eq!(alpha.on_change().listener_count(), 0);
+        alpha.set(0.2);
+        assert_eq!(display_object.alpha(), 0.1);
+
+        // Propagating a value to a target that left the tree disposes the binding.
+        root.add_child(&display_object);
+        let binding = bind(&alpha, &display_object.alpha_property());
+        display_object.remove_from_parent();
+        alpha.set(0.3);
+        assert!(binding.is_disposed());
+        assert_eq!(display_object.alpha(), 0.2);
+    }
+
+    #[test]
+    fn test_bind_ancestor_removal() {
+        let root = DisplayObject::new();
+        let container = DisplayObject::new();
+        let display_object = DisplayObject::new();
+        root.add_child(&container);
+        container.add_child(&display_object);
+        let alpha = Observable::new(0.5);
+
+        let binding = bind(&alpha, &display_object.alpha_property());
+        root.remove_child(&container);
+        crate::events::check_removed_from_tree();
+        assert!(binding.is_disposed());
+        assert_eq!(alpha.on_change().listener_count(), 0);
+        // The target is still a child of the removed container.
+        assert!(display_object.parent().is_some());
+    }
+
+    #[test]
+    fn test_bind_tree_moves() {
+        let root = DisplayObject::new();
+        let (container_1, container_2) = (DisplayObject::new(), DisplayObject::new());
+        let display_object = DisplayObject::new();
+        root.add_children([&container_1, &container_2]);
+        container_1.add_child(&display_object);
+        let alpha = Observable::new(0.5);
+
+        // Moving the target within its tree keeps the binding.
+        let binding = bind(&alpha, &display_object.alpha_property());
+        container_2.add_child(&display_object);
+        crate::events::check_removed_from_tree();
+        assert!(!binding.is_disposed());
+        alpha.set(0.25);
+        assert_eq!(display_object.alpha(), 0.25);
+
+        // Removing the target through a `Node` disposes the binding.
+        AsRef::<Node>::as_ref(&container_2).remove_child(&display_object);
+        crate::events::check_removed_from_tree();
+        assert!(binding.is_disposed());
+    }
+}
@@ -4,8 +4,20 @@ pub use display_object::*;
 mod bitmap_filter;
 pub use bitmap_filter::*;
 
+mod bitmap_data;
+pub use bitmap_data::*;
+
 mod shape;
 pub use shape::*;
 
 mod text_field;
-pub use text_field::*;
\ No newline at end of file
+pub use text_field::*;
+
+mod pointer_cursor;
+pub use pointer_cursor::*;
+
+mod performance_overlay;
+pub use performance_overlay::*;
+
+mod movie_clip;
+pub use movie_clip::*;
@@ -0,0 +1,143 @@
+use base64::Engine;
+use crate::{common::*, file::File, platforms::{if_native_platform, if_browser}};
+
+/// Decoded image pixels, stored as 8-bit RGBA rows from top to bottom.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BitmapData {
+    width: u32,
+    height: u32,
+    pixels: Bytes,
+}
+
+impl BitmapData {
+    /// Creates bitmap data from RGBA pixels. `pixels` must contain
+    /// exactly `width * height * 4` bytes.
+    pub fn new(width: u32, height: u32, pixels: Bytes) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4, "Pixel data does not match the bitmap size");
+        Self { width, height, pixels }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The RGBA pixels.
+    pub fn pixels(&self) -> Bytes {
+        self.pixels.clone()
+    }
+
+    /// Decodes a PNG, JPEG or GIF image.
+    pub fn decode(data: &[u8]) -> std::io::Result<Self> {
+        let image = image::load_from_memory(data)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?
+            .into_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            pixels: Bytes::from(image.into_raw()),
+        })
+    }
+
+    /// Loads and decodes an image asynchronously. The following URLs are supported:
+    ///
+    /// * `http:` and `https:` — Fetched from the network. A response with
+    ///   an unsuccessful status results in an error.
+    /// * `data:` — Inline data, either base64 or percent encoded.
+    /// * `file:`, `app:` and `app-storage:` — Files, read through the [`File`] API.
+    ///
+    /// Any other URL results in an `Unsupported` error.
+    pub async fn load_async(url: &str) -> std::io::Result<Self> {
+        if let Some(data_url) = url.strip_prefix("data:") {
+            return Self::decode(&decode_data_url(data_url)?);
+        }
+        if ["file:", "app:", "app-storage:"].iter().any(|scheme| url.starts_with(scheme)) {
+            return Self::decode(&File::new(url).read_bytes_async().await?);
+        }
+        if url.starts_with("http:") || url.starts_with("https:") {
+            return Self::decode(&fetch_bytes_async(url).await?);
+        }
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, format!("Unsupported image URL: '{url}'")))
+    }
+}
+
+if_native_platform! {
+    async fn fetch_bytes_async(url: &str) -> std::io::Result<Vec<u8>> {
+        let response = reqwest::get(url).await
+            .and_then(|response| response.error_for_status())
+            .map_err(std::io::Error::other)?;
+        let bytes = response.bytes().await.map_err(std::io::Error::other)?;
+        Ok(bytes.to_vec())
+    }
+}
+
+if_browser! {
+    #[wasm_bindgen::prelude::wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_name = fetch)]
+        fn js_fetch(url: &str) -> js_sys::Promise;
+    }
+
+    async fn fetch_bytes_async(url: &str) -> std::io::Result<Vec<u8>> {
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen_futures::JsFuture;
+        let js_error = |error: wasm_bindgen::JsValue| std::io::Error::other(format!("{error:?}"));
+        let response = JsFuture::from(js_fetch(url)).await.map_err(js_error)?;
+        let ok = js_sys::Reflect::get(&response, &"ok".into()).map_err(js_error)?.as_bool().unwrap_or(false);
+        if !ok {
+            let status = js_sys::Reflect::get(&response, &"status".into()).map_err(js_error)?.as_f64().unwrap_or(0.0);
+            return Err(std::io::Error::other(format!("HTTP status {status} for '{url}'")));
+        }
+        let array_buffer: js_sys::Function = js_sys::Reflect::get(&response, &"arrayBuffer".into()).map_err(js_error)?.unchecked_into();
+        let buffer = JsFuture::from(js_sys::Promise::from(array_buffer.call0(&response).map_err(js_error)?)).await.map_err(js_error)?;
+        Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+    }
+}
+
+/// Decodes the part of a `data:` URL following the scheme.
+fn decode_data_url(data_url: &str) -> std::io::Result<Vec<u8>> {
+    let Some((media_type, data)) = data_url.split_once(',') else {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Malformed data URL"));
+    };
+    if media_type.ends_with(";base64") {
+        base64::engine::general_purpose::STANDARD.decode(data.trim())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    } else {
+        Ok(percent_decode(data))
+    }
+}
+
+/// Decodes `%XX` sequences into bytes, keeping binary data intact.
+fn percent_decode(data: &str) -> Vec<u8> {
+    let bytes = data.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(octet) = data.get((i + 1)..(i + 3)).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                result.push(octet);
+                i += 3;
+                continue;
+            }
+        }
+        result.push(bytes[i]);
+        i += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_data_url() {
+        assert_eq!(decode_data_url("image/png;base64,AAEC").unwrap(), [0, 1, 2]);
+        assert_eq!(decode_data_url("text/plain,a%20b%FF").unwrap(), [b'a', b' ', b'b', 0xFF]);
+        assert!(decode_data_url("image/png;base64").is_err());
+        assert!(BitmapData::decode(b"not an image").is_err());
+    }
+}
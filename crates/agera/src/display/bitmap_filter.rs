@@ -1,10 +1,15 @@
 use crate::{
+    common::*,
     geom::*,
     util::{Color, ColorOffsets}
 };
 
 /// A bitmap filter applicable to display objects.
-#[derive(Clone)]
+/// 
+/// Serializes as an object whose `type` field is the
+/// variant name in snake case, such as `"drop_shadow"`.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum BitmapFilter {
     /// A color filter.
     Color {
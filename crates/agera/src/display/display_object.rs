@@ -1,27 +1,45 @@
-use crate::{display::*, geom::*, util::inheritance::*};
+use std::sync::Arc;
+use crate::{display::*, events::*, geom::*, util::inheritance::*};
 
 class! {
     pub struct DisplayObject: Node {
+        stored_alpha: f64 = 1.0,
+        stored_position: Vector2d = Vector2d::zero(),
+        stored_skew: Vector2d = Vector2d::zero(),
+        stored_filters: Vec<BitmapFilter> = vec![],
+        stored_scale: Vector2d = Vector2d(1.0, 1.0),
+        stored_rotation_radians: f64 = 0.0,
+        stored_rotation_x_degrees: f64 = 0.0,
+        stored_rotation_y_degrees: f64 = 0.0,
+        stored_rotation_z_degrees: f64 = 0.0,
+        stored_registration_point: Vector2d = Vector2d::zero(),
+        stored_transform_matrix: Option<Matrix2d> = None,
+    }
+}
+
+observable_properties! {
+    use crate;
+    impl DisplayObject {
         /// The opacity of a display object.
-        pub alpha: f64 = 1.0,
+        pub alpha: f64 => stored_alpha,
         /// Position relative to the parent.
-        pub position: Vector2d = Vector2d::zero(),
-        pub skew: Vector2d = Vector2d::zero(),
-        pub filters: Vec<BitmapFilter> = vec![],
-        pub scale: Vector2d = Vector2d(1.0, 1.0),
-        pub rotation_radians: f64 = 0.0,
-        pub rotation_x_degrees: f64 = 0.0,
-        pub rotation_y_degrees: f64 = 0.0,
-        pub rotation_z_degrees: f64 = 0.0,
+        pub position: Vector2d => stored_position,
+        pub skew: Vector2d => stored_skew,
+        pub filters: Vec<BitmapFilter> => stored_filters,
+        pub scale: Vector2d => stored_scale,
+        pub rotation_radians: f64 => stored_rotation_radians,
+        pub rotation_x_degrees: f64 => stored_rotation_x_degrees,
+        pub rotation_y_degrees: f64 => stored_rotation_y_degrees,
+        pub rotation_z_degrees: f64 => stored_rotation_z_degrees,
 
         /// Indicates the registration point of the display object,
         /// used when positioning and rotating the object.
         /// Each component is in the range between 0 and 1.
         /// A component value of 0.5 means center.
-        pub registration_point: Vector2d = Vector2d::zero(),
+        pub registration_point: Vector2d => stored_registration_point,
 
         /// An optional transformation matrix.
-        pub transform_matrix: Option<Matrix2d> = None,
+        pub transform_matrix: Option<Matrix2d> => stored_transform_matrix,
     }
 }
 
@@ -35,6 +53,50 @@ impl DisplayObject {
         self.clone()
     }
 
+    /// Emits changes to any observable property of the display object.
+    pub fn on_property_changed(&self) -> Arc<EventEmitter<AnyPropertyChange>> {
+        crate::events::on_property_changed(self)
+    }
+
+    /// Adds a listener for events carrying `T` dispatched to this display object
+    /// or bubbling up from its descendants.
+    pub fn add_event_listener<T, F>(&self, function: F) -> EventListener<Event<T>>
+        where
+            T: Send + Sync + 'static,
+            F: Fn(Event<T>) + Send + Sync + 'static
+    {
+        crate::events::add_event_listener(self, false, function)
+    }
+
+    /// Adds a listener for events carrying `T` in the capturing phase, invoked
+    /// before the event reaches descendants of this display object, or at target.
+    pub fn add_capture_listener<T, F>(&self, function: F) -> EventListener<Event<T>>
+        where
+            T: Send + Sync + 'static,
+            F: Fn(Event<T>) + Send + Sync + 'static
+    {
+        crate::events::add_event_listener(self, true, function)
+    }
+
+    /// Dispatches an event carrying `data`, propagating it through the ancestors
+    /// of this display object in the capture, target and bubble phases.
+    /// Returns `false` if a listener called `prevent_default()`; otherwise returns `true`.
+    pub fn dispatch_event<T>(&self, data: T) -> bool
+        where T: Send + Sync + 'static
+    {
+        crate::events::dispatch_event(self, data)
+    }
+
+    /// Emits when the display object leaves its tree, either by being removed
+    /// from its parent or by having an ancestor removed from its parent. Moving
+    /// the display object within the same tree does not emit.
+    /// 
+    /// Removals are detected once per frame by the frame loop of the
+    /// application, whether made through a `DisplayObject` or a `Node`.
+    pub fn on_removed_from_tree(&self) -> Arc<EventEmitter<()>> {
+        crate::events::on_removed_from_tree(self)
+    }
+
     /*
     /// Position relative to the root display object.
     pub fn global_position(&self) -> Vector2d {
@@ -0,0 +1,221 @@
+use std::sync::{Arc, OnceLock};
+use crate::{animation::{FrameTarget, Timeline}, common::*, display::*, timer::{self, FreeInterval, Instant}, util::inheritance::*};
+
+/// A frame script of a `MovieClip`.
+pub type FrameScript = Arc<dyn Fn(&MovieClip) + Send + Sync + 'static>;
+
+class! {
+    /// Display object whose children are animated by a `Timeline`.
+    ///
+    /// Tracks of the timeline target children by their Node path, so
+    /// children are usually named through `set_name()` before being added.
+    ///
+    /// A clip is initially stopped at the first frame. Once played, it
+    /// advances at the timeline's frame rate and loops back to the first
+    /// frame after the last frame. Entering a frame applies the timeline
+    /// state of that frame and then calls its frame scripts.
+    ///
+    /// ```ignore
+    /// let clip = MovieClip::new(timeline);
+    /// clip.add_child(&ball);
+    /// clip.add_frame_script(clip.timeline().label_frame("end").unwrap(), |clip| clip.stop());
+    /// clip.goto_and_play("intro");
+    /// ```
+    pub struct MovieClip: DisplayObject < Node {
+        stored_timeline: Arc<Timeline> = Arc::new(Timeline::new(24.0, 1)),
+        stored_current_frame: u32 = 0,
+        /// The time at which the current frame was entered while playing.
+        frame_time: Instant = Instant::now(),
+        interval: Option<Arc<FreeInterval>> = None,
+        frame_scripts: HashMap<u32, Vec<FrameScript>> = HashMap::new(),
+    }
+
+    pub fn constructor(timeline: Timeline) {
+        super();
+        this.set_timeline(timeline);
+    }
+}
+
+impl MovieClip {
+    pub fn timeline(&self) -> Arc<Timeline> {
+        self.stored_timeline()
+    }
+
+    /// Replaces the timeline, stopping at its first frame.
+    pub fn set_timeline(&self, timeline: Timeline) {
+        self.stop();
+        self.set_stored_timeline(Arc::new(timeline));
+        self.enter_frame(0);
+    }
+
+    /// The zero based current frame.
+    pub fn current_frame(&self) -> u32 {
+        self.stored_current_frame()
+    }
+
+    /// The label of the current frame. See [`Timeline::label_at`].
+    pub fn current_label(&self) -> Option<String> {
+        self.timeline().label_at(self.current_frame()).map(str::to_owned)
+    }
+
+    pub fn total_frames(&self) -> u32 {
+        self.timeline().frames()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.interval().is_some()
+    }
+
+    /// Plays the clip from the current frame.
+    pub fn play(&self) {
+        if self.is_playing() {
+            return;
+        }
+        self.set_frame_time(Instant::now());
+        let slot = Arc::new(OnceLock::<Arc<FreeInterval>>::new());
+        let clip = self.downgrade_ref();
+        let interval = Arc::new(timer::free_animation_interval(Box::new({
+            let slot = slot.clone();
+            move |_| {
+                match clip.upgrade().and_then(|clip| MovieClip::try_from(clip).ok()) {
+                    Some(clip) => clip.advance(),
+                    None => if let Some(interval) = slot.get() {
+                        interval.stop();
+                    },
+                }
+            }
+        }), self.timeline().frame_duration()));
+        let _ = slot.set(interval.clone());
+        self.set_interval(Some(interval));
+    }
+
+    /// Stops the clip at the current frame.
+    pub fn stop(&self) {
+        if let Some(interval) = self.interval() {
+            interval.stop();
+            self.set_interval(None);
+        }
+    }
+
+    /// Goes to a frame or label and plays from there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame is out of bounds or the label does not exist.
+    pub fn goto_and_play(&self, target: impl Into<FrameTarget>) {
+        let frame = self.resolve(target.into());
+        self.stop();
+        self.play();
+        self.enter_frame(frame);
+    }
+
+    /// Goes to a frame or label and stops there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame is out of bounds or the label does not exist.
+    pub fn goto_and_stop(&self, target: impl Into<FrameTarget>) {
+        let frame = self.resolve(target.into());
+        self.stop();
+        self.enter_frame(frame);
+    }
+
+    /// Stops at the next frame, if any.
+    pub fn next_frame(&self) {
+        self.stop();
+        if self.current_frame() + 1 < self.total_frames() {
+            self.enter_frame(self.current_frame() + 1);
+        }
+    }
+
+    /// Stops at the previous frame, if any.
+    pub fn prev_frame(&self) {
+        self.stop();
+        if let Some(frame) = self.current_frame().checked_sub(1) {
+            self.enter_frame(frame);
+        }
+    }
+
+    /// Adds a function called whenever the clip enters `frame`.
+    pub fn add_frame_script(&self, frame: u32, script: impl Fn(&MovieClip) + Send + Sync + 'static) {
+        let mut scripts = self.frame_scripts();
+        scripts.entry(frame).or_default().push(Arc::new(script));
+        self.set_frame_scripts(scripts);
+    }
+
+    /// Removes the frame scripts of `frame`.
+    pub fn clear_frame_scripts(&self, frame: u32) {
+        let mut scripts = self.frame_scripts();
+        scripts.remove(&frame);
+        self.set_frame_scripts(scripts);
+    }
+
+    fn resolve(&self, target: FrameTarget) -> u32 {
+        match self.timeline().resolve(&target) {
+            Some(frame) => frame,
+            None => panic!("Frame not found in timeline: {target:?}"),
+        }
+    }
+
+    fn enter_frame(&self, frame: u32) {
+        self.set_stored_current_frame(frame);
+        self.timeline().apply(self, frame);
+        for script in self.frame_scripts().get(&frame).cloned().unwrap_or_default() {
+            script(self);
+        }
+    }
+
+    /// Enters every frame due since the current frame was entered.
+    fn advance(&self) {
+        let frame_duration = self.timeline().frame_duration();
+        let now = Instant::now();
+        while self.is_playing() && now.since(self.frame_time()) >= frame_duration {
+            self.set_frame_time(self.frame_time() + frame_duration);
+            let next = (self.current_frame() + 1) % self.total_frames();
+            self.enter_frame(next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::{animation::*, common::*, display::*, timer::{testing::MockClock, Duration}};
+
+    #[test]
+    fn test_movie_clip() {
+        let clock = MockClock::install();
+        let ball = DisplayObject::new();
+        ball.set_name(Some("ball".into()));
+        let clip = MovieClip::new(Timeline::new(10.0, 4)
+            .label("end", 3)
+            .keyframe("ball", Keyframe::new(0, ChildState { alpha: Some(0.0), ..default() }).easing(Easing::Linear))
+            .keyframe("ball", Keyframe::new(2, ChildState { alpha: Some(1.0), ..default() })));
+        clip.add_child(&ball);
+
+        let entered = Arc::new(Mutex::new(vec![]));
+        clip.add_frame_script(3, {
+            let entered = entered.clone();
+            move |clip| {
+                entered.lock().unwrap().push(clip.current_frame());
+                clip.stop();
+            }
+        });
+
+        clip.play();
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(clip.current_frame(), 1);
+        assert_eq!(ball.alpha(), 0.5);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(clip.current_frame(), 3);
+        assert_eq!(clip.current_label().as_deref(), Some("end"));
+        assert!(!clip.is_playing());
+        assert_eq!(*entered.lock().unwrap(), [3]);
+
+        clip.goto_and_stop(0);
+        assert_eq!(ball.alpha(), 0.0);
+        clip.goto_and_play("end");
+        assert!(!clip.is_playing());
+        assert_eq!(*entered.lock().unwrap(), [3, 3]);
+    }
+}
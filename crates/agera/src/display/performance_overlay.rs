@@ -0,0 +1,86 @@
+use crate::{application::{frame_stats, FramePhase, FRAME_HISTORY_LENGTH}, common::*, display::*, events::observable_properties, geom::*, util::inheritance::*};
+
+const BACKGROUND: [u8; 4] = [0, 0, 0, 160];
+const WITHIN_BUDGET: [u8; 4] = [80, 200, 120, 255];
+const OVER_BUDGET: [u8; 4] = [240, 190, 60, 255];
+const DROPPED: [u8; 4] = [230, 70, 70, 255];
+const BUDGET_LINE: [u8; 4] = [255, 255, 255, 200];
+
+class! {
+    /// Displays the application's frame statistics as a graph of
+    /// recent frame times, intended for debugging performance.
+    /// 
+    /// Each bar of the graph is a frame, whose height is its interval,
+    /// colored green within the frame budget, yellow over the frame budget
+    /// and red for a frame after dropped frames. A horizontal line
+    /// marks the frame budget, placed at a third of the height.
+    /// 
+    /// ```ignore
+    /// let overlay = PerformanceOverlay::new();
+    /// application::root().add_child(&overlay);
+    /// println!("{}", overlay.summary());
+    /// ```
+    pub struct PerformanceOverlay: DisplayObject < Node {
+        stored_size: Vector2d = Vector2d(240.0, 60.0),
+    }
+}
+
+observable_properties! {
+    use crate;
+    impl PerformanceOverlay {
+        pub size: Vector2d => stored_size,
+    }
+}
+
+impl PerformanceOverlay {
+    /// A single-line text summary of the frame statistics, such as
+    /// `60.0 FPS, 16.7 ms (events 0.4 ms, layout 1.2 ms, render 3.1 ms), 0 dropped`.
+    pub fn summary(&self) -> String {
+        let stats = frame_stats();
+        let millis = |duration: crate::timer::Duration| duration.as_secs_f64() * 1000.0;
+        format!(
+            "{:.1} FPS, {:.1} ms (events {:.1} ms, layout {:.1} ms, render {:.1} ms), {} dropped",
+            stats.fps(),
+            millis(stats.average_frame_time()),
+            millis(stats.average_phase_time(FramePhase::Events)),
+            millis(stats.average_phase_time(FramePhase::Layout)),
+            millis(stats.average_phase_time(FramePhase::Render)),
+            stats.dropped_frames(),
+        )
+    }
+
+    /// Draws the graph of recent frame times at the overlay's size.
+    pub fn render(&self) -> BitmapData {
+        let size = self.size();
+        let (width, height) = (size.x().round().max(1.0) as u32, size.y().round().max(1.0) as u32);
+        let mut pixels = BACKGROUND.repeat(width as usize * height as usize);
+        let mut put = |x: u32, y: u32, color: [u8; 4]| {
+            let i = (y as usize * width as usize + x as usize) * 4;
+            pixels[i..i + 4].copy_from_slice(&color);
+        };
+
+        let stats = frame_stats();
+        let budget = stats.frame_budget().as_secs_f64();
+        let budget_y = height - 1 - (height - 1) / 3;
+        let bar_width = (width as f64 / FRAME_HISTORY_LENGTH as f64).max(1.0);
+        let frames = stats.recent_frames();
+        let visible = frames.len().min((width as f64 / bar_width) as usize);
+        for (i, timing) in frames[frames.len() - visible..].iter().enumerate() {
+            let ratio = timing.interval.as_secs_f64() / budget;
+            let bar_height = ((ratio / 3.0) * height as f64).round().min(height as f64) as u32;
+            let color = if timing.dropped > 0 { DROPPED } else if ratio > 1.0 { OVER_BUDGET } else { WITHIN_BUDGET };
+            let x0 = (i as f64 * bar_width) as u32;
+            let x1 = (((i + 1) as f64 * bar_width) as u32).min(width);
+            for x in x0..x1 {
+                for y in height - bar_height..height {
+                    put(x, y, color);
+                }
+            }
+        }
+        for x in 0..width {
+            put(x, budget_y, BUDGET_LINE);
+        }
+
+        BitmapData::new(width, height, Bytes::from(pixels))
+    }
+}
@@ -0,0 +1,10 @@
+/// Appearance of the pointer cursor over a display object.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PointerCursor {
+    /// The platform default cursor, usually an arrow.
+    Default,
+    /// A hand, indicating a link.
+    Pointer,
+    /// An I-beam, indicating text.
+    Text,
+}
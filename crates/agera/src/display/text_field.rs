@@ -1,4 +1,5 @@
-use crate::{common::*, display::*, geom::*, text::*, util::inheritance::*};
+use std::sync::Arc;
+use crate::{common::*, display::*, events::observable_properties, geom::*, text::{*, layout::*}, util::inheritance::*};
 
 class! {
     /// Represents a text field of fixed size.
@@ -15,10 +16,11 @@ class! {
     /// | `<i></i>`          | Italic |
     /// | `<s></s>`          | Strikethrough |
     /// | `<u></u>`          | Underline text |
-    /// | `<img>`            | Image, with support for `http:`, `https:`, `data:` and `file:` URLs |
+    /// | `<img>`            | Image, with support for `http:`, `https:`, `data:`, `file:`, `app:` and `app-storage:` URLs |
     /// | `<sup></sup>`      | Superscript |
     /// | `<sub></sub>`      | Subscript |
-    /// | `<a></a>`          | Anchor, supporting the `href` attribute |
+    /// | `<a></a>`          | Anchor, supporting the `href` attribute and the `event:` scheme |
+    /// | `<span></span>`    | Span, supporting the `class` attribute for class styles |
     /// | `<ul></ul>`        | Unordered list |
     /// | `<ol></ol>`        | Ordered list |
     /// | `<li></li>`        | List item |
@@ -28,15 +30,65 @@ class! {
     /// | `<hr>`              | Horizontal ruler |
     /// | `<br>`              | Break |
     /// 
+    /// # Images
+    /// 
+    /// Images support the `src`, `width`, `height` and `align` attributes.
+    /// They load asynchronously as the HTML text is assigned, and the text
+    /// reflows once each image loads, emitting the `on_image_load()` event.
+    /// Until then, an image reserves the space given by its `width` and
+    /// `height` attributes.
+    /// 
+    /// An image that fails to load emits the `on_image_error()` event
+    /// and is requested again by `reload_images()` or the next time
+    /// the HTML text changes.
+    /// 
+    /// An `align` attribute of `left` or `right` floats the image
+    /// to that edge, with the text wrapping around it.
+    /// 
+    /// ```ignore
+    /// text_field.set_html(r#"Hello <img src="app://emoji/wave.png" width="16" height="16">"#.into());
+    /// ```
+    /// 
+    /// # Links
+    /// 
+    /// Clicking an anchor emits its `href` through the `on_link()` event emitter.
+    /// The text field does not navigate by itself. Anchors using the `event:`
+    /// pseudo-scheme, such as `<a href="event:help">`, are intended for in-app
+    /// actions, whose name is given by `TextLinkEvent::action()`.
+    /// 
+    /// ```ignore
+    /// text_field.set_html(r#"See <a href="event:settings">settings</a>."#.into());
+    /// text_field.on_link().listener(|e| {
+    ///     if e.action() == Some("settings") {
+    ///         // Action
+    ///     }
+    /// });
+    /// ```
+    /// 
+    /// While the pointer is over an anchor, that anchor is styled with
+    /// the `anchor_hover` style and the text field's cursor becomes
+    /// `PointerCursor::Pointer`.
+    /// 
     pub struct TextField: DisplayObject < Node {
-        pub size: Vector2d = Vector2d(100.0, 50.0),
+        html_text: String = String::new(),
+        stored_size: Vector2d = Vector2d(100.0, 50.0),
         pub ref style_sheet: StyleSheetContainer = default(),
-        pub horizontal_alignment: TextHorizontalAlignment = TextHorizontalAlignment::Left,
-        pub vertical_alignment: TextVerticalAlignment = TextVerticalAlignment::Top,
-        /// Horizontal scroll, in pixels.
-        pub horizontal_scroll: f64 = 0.0,
-        /// Vertical scroll, in pixels.
-        pub vertical_scroll: f64 = 0.0,
+        stored_horizontal_alignment: TextHorizontalAlignment = TextHorizontalAlignment::Left,
+        stored_vertical_alignment: TextVerticalAlignment = TextVerticalAlignment::Top,
+        stored_horizontal_scroll: f64 = 0.0,
+        stored_vertical_scroll: f64 = 0.0,
+        /// Emits when an anchor is clicked.
+        pub ref on_link: EventEmitter<TextLinkEvent> = EventEmitter::new(),
+        /// Emits the `href` of the anchor under the pointer when it changes,
+        /// or `None` when the pointer leaves an anchor.
+        pub ref on_link_hover: EventEmitter<Option<String>> = EventEmitter::new(),
+        /// Emits the source of an image after it loads.
+        pub ref on_image_load: EventEmitter<String> = EventEmitter::new(),
+        /// Emits when an image fails to load.
+        pub ref on_image_error: EventEmitter<TextImageErrorEvent> = EventEmitter::new(),
+        hovered_href: Option<String> = None,
+        /// Requested images by source, which are `None` while loading.
+        images: HashMap<String, Option<Arc<BitmapData>>> = HashMap::new(),
     }
 
     pub fn constructor(text: String) {
@@ -45,6 +97,215 @@ class! {
     }
 }
 
+observable_properties! {
+    use crate;
+    impl TextField {
+        /// The text content in the HTML dialect described above.
+        pub html: String => stored_html,
+        pub size: Vector2d => stored_size,
+        pub horizontal_alignment: TextHorizontalAlignment => stored_horizontal_alignment,
+        pub vertical_alignment: TextVerticalAlignment => stored_vertical_alignment,
+        /// Horizontal scroll, in pixels.
+        pub horizontal_scroll: f64 => stored_horizontal_scroll,
+        /// Vertical scroll, in pixels.
+        pub vertical_scroll: f64 => stored_vertical_scroll,
+    }
+}
+
+impl TextField {
+    /// The text content without HTML markup. Paragraphs and
+    /// line breaks are delimited by line feeds.
+    pub fn text(&self) -> String {
+        markup::plain_text(&self.html())
+    }
+
+    /// Sets the text content from plain text. Line feeds
+    /// are taken as line breaks.
+    pub fn set_text(&self, value: String) -> Self {
+        self.set_html(plain_text_to_html(&value))
+    }
+
+    /// The number of lines of the text field, as laid out
+    /// at the width of the text field.
+    pub fn line_count(&self) -> usize {
+        self.layout().lines.len()
+    }
+
+    /// The `href` of the anchor under the pointer, if any.
+    pub fn hovered_link(&self) -> Option<String> {
+        self.hovered_href()
+    }
+
+    /// The cursor to display while the pointer is over the text field.
+    pub fn cursor(&self) -> PointerCursor {
+        if self.hovered_href().is_some() { PointerCursor::Pointer } else { PointerCursor::Default }
+    }
+
+    /// Returns the `href` of the anchor at `position`, relative
+    /// to the text field, if any.
+    pub fn link_at(&self, position: Vector2d) -> Option<String> {
+        let layout = self.layout();
+        // The layout is in points, while the text field is in pixels.
+        let height = self.size().y() * POINTS_PER_PIXEL;
+        let vertical_offset = match self.vertical_alignment() {
+            TextVerticalAlignment::Top => 0.0,
+            TextVerticalAlignment::Center => ((height - layout.height) / 2.0).max(0.0),
+            TextVerticalAlignment::Bottom => (height - layout.height).max(0.0),
+        };
+        let position = (position + Vector2d(self.horizontal_scroll(), self.vertical_scroll())) * POINTS_PER_PIXEL - Vector2d(0.0, vertical_offset);
+        layout.href_at(position).map(|href| href.to_owned())
+    }
+
+    /// Updates the hovered anchor as the pointer moves to `position`,
+    /// relative to the text field. This method is called by the
+    /// input handling of the platform.
+    pub fn handle_pointer_move(&self, position: Vector2d) {
+        self.set_hovered_link(self.link_at(position));
+    }
+
+    /// Clears the hovered anchor as the pointer leaves the text field.
+    /// This method is called by the input handling of the platform.
+    pub fn handle_pointer_leave(&self) {
+        self.set_hovered_link(None);
+    }
+
+    /// Emits the `on_link()` event if `position`, relative to the text field,
+    /// is over an anchor. This method is called by the input handling of the platform.
+    pub fn handle_click(&self, position: Vector2d) {
+        if let Some(href) = self.link_at(position) {
+            self.on_link().emit(TextLinkEvent { href });
+        }
+    }
+
+    fn set_hovered_link(&self, href: Option<String>) {
+        if self.hovered_href() != href {
+            self.set_hovered_href(href.clone());
+            self.on_link_hover().emit(href);
+        }
+    }
+
+    /// Returns a loaded image by its source.
+    pub fn image(&self, source: &str) -> Option<Arc<BitmapData>> {
+        self.images().get(source).cloned().flatten()
+    }
+
+    fn stored_html(&self) -> String {
+        self.html_text()
+    }
+
+    /// Stores the HTML text and starts loading the images it references.
+    fn set_stored_html(&self, value: String) {
+        self.set_html_text(value);
+        self.load_images();
+    }
+
+    /// Requests again the images that failed to load.
+    pub fn reload_images(&self) {
+        self.load_images();
+    }
+
+    /// Starts loading the images of the HTML text that
+    /// have not been requested yet.
+    fn load_images(&self) {
+        self.load_images_with(|source| async move { BitmapData::load_async(&source).await });
+    }
+
+    /// Requests the images that have not been requested yet through `load`.
+    /// Each load updates only the entry of its own source once it completes,
+    /// so that loads completing in any order keep each other's results.
+    fn load_images_with<F>(&self, load: impl Fn(String) -> F)
+        where F: std::future::Future<Output = std::io::Result<BitmapData>> + 'static
+    {
+        let mut images = self.images();
+        let mut new_sources = vec![];
+        for source in markup::image_sources(&self.html_text()) {
+            if !images.contains_key(&source) {
+                images.insert(source.clone(), None);
+                new_sources.push(source);
+            }
+        }
+        if new_sources.is_empty() {
+            return;
+        }
+        self.set_images(images);
+        for source in new_sources {
+            let text_field = self.clone();
+            let load = load(source.clone());
+            future::exec(async move {
+                let result = load.await;
+                let mut images = text_field.images();
+                match result {
+                    Ok(bitmap) => {
+                        images.insert(source.clone(), Some(Arc::new(bitmap)));
+                        text_field.set_images(images);
+                        text_field.on_image_load().emit(source);
+                    },
+                    Err(error) => {
+                        // Forget the request so that the image is requested again.
+                        images.remove(&source);
+                        text_field.set_images(images);
+                        text_field.on_image_error().emit(TextImageErrorEvent { source, message: error.to_string() });
+                    },
+                }
+            });
+        }
+    }
+
+    pub(crate) fn layout(&self) -> TextLayout {
+        let image_sizes = self.images().into_iter()
+            .filter_map(|(source, bitmap)| bitmap.map(|bitmap| (source, Vector2d(bitmap.width() as f64, bitmap.height() as f64) * POINTS_PER_PIXEL)))
+            .collect();
+        layout_html(&self.html(), &self.style_sheet(), &LayoutOptions {
+            max_width: Some(self.size().x() * POINTS_PER_PIXEL),
+            align: match self.horizontal_alignment() {
+                TextHorizontalAlignment::Left => TextAlign::Left,
+                TextHorizontalAlignment::Center => TextAlign::Center,
+                TextHorizontalAlignment::Right => TextAlign::Right,
+            },
+            hovered_href: self.hovered_href(),
+            image_sizes,
+        })
+    }
+}
+
+/// Event emitted when an anchor of a `TextField` is clicked.
+#[derive(Clone, Debug)]
+pub struct TextLinkEvent {
+    href: String,
+}
+
+impl TextLinkEvent {
+    /// The `href` attribute of the anchor.
+    pub fn href(&self) -> &str {
+        &self.href
+    }
+
+    /// For anchors using the `event:` pseudo-scheme, the action
+    /// name following the scheme.
+    pub fn action(&self) -> Option<&str> {
+        self.href.strip_prefix("event:")
+    }
+}
+
+/// Event emitted when an image of a `TextField` fails to load.
+#[derive(Clone, Debug)]
+pub struct TextImageErrorEvent {
+    source: String,
+    message: String,
+}
+
+impl TextImageErrorEvent {
+    /// The `src` attribute of the image.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// A description of the failure.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum TextHorizontalAlignment {
     Left,
@@ -57,4 +318,117 @@ pub enum TextVerticalAlignment {
     Top,
     Center,
     Bottom,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use base64::Engine;
+    use crate::{common::*, display::*, geom::*, timer::testing::MockClock};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = std::io::Cursor::new(vec![]);
+        image::RgbaImage::new(width, height).write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+        png.into_inner()
+    }
+
+    #[test]
+    fn test_images() {
+        let clock = MockClock::install();
+        let source = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png(3, 2)));
+
+        let text_field = TextField::new(String::new());
+        let loaded = Arc::new(RwLock::new(vec![]));
+        let failed = Arc::new(RwLock::new(vec![]));
+        text_field.on_image_load().listener({
+            let loaded = Arc::clone(&loaded);
+            move |source| loaded.write().unwrap().push(source)
+        });
+        text_field.on_image_error().listener({
+            let failed = Arc::clone(&failed);
+            move |e| failed.write().unwrap().push(e.source().to_owned())
+        });
+
+        text_field.set_html(format!(r#"<img src="{source}"><img src="data:image/png;base64,AAEC">"#));
+        // Laying out does not request images again.
+        text_field.line_count();
+        clock.run_until_idle();
+        assert_eq!(*loaded.read().unwrap(), vec![source.clone()]);
+        assert_eq!(*failed.read().unwrap(), ["data:image/png;base64,AAEC"]);
+        assert_eq!(text_field.image(&source).map(|bitmap| (bitmap.width(), bitmap.height())), Some((3, 2)));
+
+        text_field.reload_images();
+        clock.run_until_idle();
+        assert_eq!(loaded.read().unwrap().len(), 1);
+        assert_eq!(failed.read().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_images_out_of_order() {
+        let clock = MockClock::install();
+        let text_field = TextField::new(String::new());
+        text_field.set_html_text(r#"<img src="file:///a.png"><img src="file:///b.png">"#.into());
+        let senders = Arc::new(RwLock::new(HashMap::new()));
+        text_field.load_images_with({
+            let senders = Arc::clone(&senders);
+            move |source| {
+                let (sender, receiver) = futures::channel::oneshot::channel();
+                senders.write().unwrap().insert(source, sender);
+                async move { receiver.await.unwrap() }
+            }
+        });
+        clock.run_until_idle();
+
+        let complete = |source: &str, size: u32| {
+            let sender = senders.write().unwrap().remove(source).unwrap();
+            let _ = sender.send(BitmapData::decode(&png(size, size)));
+            clock.run_until_idle();
+        };
+        complete("file:///b.png", 2);
+        complete("file:///a.png", 1);
+        assert_eq!(text_field.image("file:///a.png").map(|bitmap| bitmap.width()), Some(1));
+        assert_eq!(text_field.image("file:///b.png").map(|bitmap| bitmap.width()), Some(2));
+    }
+
+    #[test]
+    fn test_wrap_width() {
+        // Text fields are sized in pixels, while text is measured in points.
+        let text = "aaaa bbbb";
+        let width = crate::text::measure_plain_text(text, &Default::default(), None).width;
+        let text_field = TextField::new(text.into());
+        text_field.set_size(Vector2d(width / crate::text::layout::POINTS_PER_PIXEL, 50.0));
+        assert_eq!(text_field.line_count(), 1);
+        text_field.set_size(Vector2d(width, 50.0));
+        assert_eq!(text_field.line_count(), 2);
+    }
+
+    #[test]
+    fn test_links() {
+        let text_field = TextField::new(String::new());
+        text_field.set_size(Vector2d(200.0, 50.0));
+        text_field.set_html(r#"<a href="event:help">Help</a> and more"#.into());
+
+        let hovered = Arc::new(RwLock::new(vec![]));
+        let clicked = Arc::new(RwLock::new(vec![]));
+        text_field.on_link_hover().listener({
+            let hovered = Arc::clone(&hovered);
+            move |href| hovered.write().unwrap().push(href)
+        });
+        text_field.on_link().listener({
+            let clicked = Arc::clone(&clicked);
+            move |e| clicked.write().unwrap().push(e.action().map(|action| action.to_owned()))
+        });
+
+        text_field.handle_pointer_move(Vector2d(5.0, 5.0));
+        assert_eq!(text_field.hovered_link().as_deref(), Some("event:help"));
+        assert_eq!(text_field.cursor(), PointerCursor::Pointer);
+        text_field.handle_pointer_move(Vector2d(6.0, 5.0));
+        text_field.handle_pointer_move(Vector2d(150.0, 5.0));
+        assert_eq!(text_field.cursor(), PointerCursor::Default);
+        assert_eq!(*hovered.read().unwrap(), [Some("event:help".to_owned()), None]);
+
+        text_field.handle_click(Vector2d(5.0, 5.0));
+        text_field.handle_click(Vector2d(150.0, 5.0));
+        assert_eq!(*clicked.read().unwrap(), [Some("help".to_owned())]);
+    }
 }
\ No newline at end of file
@@ -11,7 +11,104 @@ button.on_click().listener(|e| {
     // Action
 });
 ```
+
+In asynchronous code, the next emitted value can be awaited through
+the `.next` method, and emitted values can be consumed as a
+`futures::Stream` through the `.stream` method.
+
+```ignore
+let mut clicks = button.on_click().stream();
+while let Some(click) = clicks.next().await {
+    // Action
+}
+```
+
+Event emitters can be derived from other event emitters through
+combinators such as `.map`, `.filter`, `.merge`, `.debounce`,
+`.throttle` and `.distinct_until_changed`.
+
+```ignore
+let resized = window.on_resize().throttle(Duration::from_millis(100));
+```
+
+Events that do not belong to a particular object can be published
+through the application-wide event bus in `agera::events::bus`, keyed by event type.
+
+```ignore
+bus::subscribe::<ThemeChanged>(|e| {
+    // Action
+});
+bus::publish(ThemeChanged);
+```
+
+# Propagation
+
+Events dispatched to a display object through `dispatch_event()` propagate
+through the node graph, as in the DOM: capture listeners of the ancestors are
+invoked from the root down, then the listeners of the target, then the
+non-capture listeners of the ancestors from the parent up. Listeners receive
+an `Event`, which supports `stop_propagation()`, `stop_immediate_propagation()`
+and `prevent_default()`.
+
+```ignore
+#[derive(Clone)]
+struct Click;
+
+container.add_event_listener(|e: Event<Click>| {
+    if e.target() != e.current_target() {
+        // Delegated action
+    }
+});
+button.dispatch_event(Click);
+```
 */
 
 mod event_emitter;
-pub use self::event_emitter::*;
\ No newline at end of file
+pub use self::event_emitter::*;
+
+mod combinators;
+
+mod event_stream;
+pub use self::event_stream::*;
+
+mod property;
+pub use self::property::*;
+
+/// Generates observable accessors for fields of a class declared through `class!`.
+/// 
+/// For each property, the field is stored in a private `class!` field and this macro
+/// generates a getter, a setter that emits changes, an `on_<name>_changed()`
+/// event emitter, and a `<name>_property()` method returning the property for
+/// use with `agera::binding`. Setting a property to a value equal to its current value emits nothing.
+/// Changes are also emitted through `agera::events::on_property_changed()`, which
+/// display objects expose as `on_property_changed()`.
+/// 
+/// # Syntax
+/// 
+/// ```ignore
+/// use agera::{events::*, util::inheritance::*};
+/// 
+/// class! {
+///     pub struct Counter: Node {
+///         stored_count: u64 = 0,
+///     }
+/// }
+/// 
+/// observable_properties! {
+///     impl Counter {
+///         /// The current count.
+///         pub count: u64 => stored_count,
+///     }
+/// }
+/// 
+/// counter.on_count_changed().listener(|change| {
+///     println!("{} -> {}", change.old_value, change.new_value);
+/// });
+/// counter.set_count(1);
+/// ```
+pub use agera_sdk_proc::observable_properties;
+
+mod propagation;
+pub use self::propagation::*;
+
+pub mod bus;
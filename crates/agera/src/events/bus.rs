@@ -0,0 +1,164 @@
+/*!
+A typed application-wide event bus.
+
+The bus holds one event emitter per event type, so that modules
+can communicate without owning a common event emitter.
+
+```ignore
+use agera::events::bus;
+
+#[derive(Clone)]
+struct LoggedOut;
+
+let listener = bus::subscribe::<LoggedOut>(|_| {
+    // Action
+});
+bus::publish(LoggedOut);
+```
+
+# Testing
+
+Unit tests can use a fresh bus through `bus::isolate()`, which makes
+the current thread use an empty bus until the returned guard is dropped:
+
+```ignore
+let _bus = bus::isolate();
+bus::publish(LoggedOut);
+```
+*/
+
+use std::{any::{Any, TypeId}, cell::RefCell, sync::{Arc, PoisonError, RwLock}};
+use crate::common::*;
+
+static GLOBAL: Lazy<Arc<Bus>> = Lazy::new(|| Arc::new(Bus::new()));
+
+thread_local! {
+    /// Buses installed by `isolate()` on the current thread, from the outermost to the innermost.
+    static ISOLATED: RefCell<Vec<Arc<Bus>>> = const { RefCell::new(vec![]) };
+}
+
+/// An event bus, holding one event emitter per event type.
+/// 
+/// The application-wide bus is used through the functions
+/// of this module; separate buses can be created through `Bus::new()`.
+pub struct Bus {
+    emitters: RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Self {
+            emitters: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the event emitter for events of type `T`.
+    pub fn emitter<T: Clone + Send + Sync + 'static>(&self) -> Arc<EventEmitter<T>> {
+        if let Some(emitter) = self.emitters.read().unwrap_or_else(PoisonError::into_inner).get(&TypeId::of::<T>()) {
+            return Arc::clone(emitter).downcast::<EventEmitter<T>>().unwrap();
+        }
+        let mut emitters = self.emitters.write().unwrap_or_else(PoisonError::into_inner);
+        let emitter = emitters.entry(TypeId::of::<T>()).or_insert_with(|| Arc::new(EventEmitter::<T>::new()));
+        Arc::clone(emitter).downcast::<EventEmitter<T>>().unwrap()
+    }
+
+    /// Emits an event to the listeners of its type.
+    pub fn publish<T: Clone + Send + Sync + 'static>(&self, event: T) {
+        self.emitter::<T>().emit(event);
+    }
+
+    /// Adds a listener to events of type `T`.
+    pub fn subscribe<T: Clone + Send + Sync + 'static>(&self, function: impl Fn(T) + Send + Sync + 'static) -> EventListener<T> {
+        self.emitter::<T>().listener(function)
+    }
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the bus used by the current thread: the innermost
+/// bus installed by `isolate()`, or else the application-wide bus.
+pub fn current() -> Arc<Bus> {
+    ISOLATED.with(|isolated| isolated.borrow().last().cloned()).unwrap_or_else(|| Arc::clone(&GLOBAL))
+}
+
+/// Returns the event emitter for events of type `T` in the current bus.
+pub fn emitter<T: Clone + Send + Sync + 'static>() -> Arc<EventEmitter<T>> {
+    current().emitter::<T>()
+}
+
+/// Emits an event to the listeners of its type in the current bus.
+pub fn publish<T: Clone + Send + Sync + 'static>(event: T) {
+    current().publish(event);
+}
+
+/// Adds a listener to events of type `T` in the current bus.
+pub fn subscribe<T: Clone + Send + Sync + 'static>(function: impl Fn(T) + Send + Sync + 'static) -> EventListener<T> {
+    current().subscribe(function)
+}
+
+/// Makes the current thread use a fresh bus until the returned guard is dropped.
+/// This is intended for unit tests, which otherwise share the application-wide bus.
+pub fn isolate() -> IsolatedBus {
+    let bus = Arc::new(Bus::new());
+    ISOLATED.with(|isolated| isolated.borrow_mut().push(Arc::clone(&bus)));
+    IsolatedBus { bus }
+}
+
+/// A bus installed by `isolate()`, uninstalled on drop.
+pub struct IsolatedBus {
+    bus: Arc<Bus>,
+}
+
+impl IsolatedBus {
+    pub fn bus(&self) -> &Arc<Bus> {
+        &self.bus
+    }
+}
+
+impl Drop for IsolatedBus {
+    fn drop(&mut self) {
+        ISOLATED.with(|isolated| {
+            let mut isolated = isolated.borrow_mut();
+            if let Some(i) = isolated.iter().rposition(|bus| Arc::ptr_eq(bus, &self.bus)) {
+                isolated.remove(i);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    use crate::events::bus;
+
+    #[derive(Clone)]
+    struct LoggedOut;
+
+    #[test]
+    fn test_bus() {
+        let outer = bus::isolate();
+        let count = Arc::new(AtomicUsize::new(0));
+        let listener = bus::subscribe::<LoggedOut>({
+            let count = Arc::clone(&count);
+            move |_| { count.fetch_add(1, Ordering::SeqCst); }
+        });
+        bus::publish(LoggedOut);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // A nested isolated bus does not see the outer listeners.
+        {
+            let _inner = bus::isolate();
+            bus::publish(LoggedOut);
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+        }
+
+        listener.remove();
+        bus::publish(LoggedOut);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(outer.bus().emitter::<LoggedOut>().listener_count(), 0);
+    }
+}
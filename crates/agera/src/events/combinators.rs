@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex, Weak};
+use crate::{common::*, timer::{self, Duration, FreeTimeout, Instant}};
+
+/// Combinators deriving event emitters from other event emitters.
+///
+/// A derived event emitter keeps receiving values for as long as it or any
+/// of its listeners is alive. Once it is no longer referenced, its listener
+/// on the source event emitter removes itself.
+impl<T: Clone + Send + Sync + 'static> EventEmitter<T> {
+    /// Returns an event emitter that emits the values of this event
+    /// emitter transformed by `function`.
+    pub fn map<U, F>(&self, function: F) -> EventEmitter<U>
+        where
+            U: Clone + Send + Sync + 'static,
+            F: Fn(T) -> U + Send + Sync + 'static
+    {
+        let derived = EventEmitter::new();
+        self.forward_to(&derived, move |data, derived| derived.emit(function(data)));
+        derived
+    }
+
+    /// Returns an event emitter that emits the values of this event
+    /// emitter for which `predicate` returns `true`.
+    pub fn filter<F>(&self, predicate: F) -> EventEmitter<T>
+        where F: Fn(&T) -> bool + Send + Sync + 'static
+    {
+        let derived = EventEmitter::new();
+        self.forward_to(&derived, move |data, derived| {
+            if predicate(&data) {
+                derived.emit(data);
+            }
+        });
+        derived
+    }
+
+    /// Returns an event emitter that emits the values of both
+    /// this event emitter and `other`.
+    pub fn merge(&self, other: &EventEmitter<T>) -> EventEmitter<T> {
+        let derived = EventEmitter::new();
+        self.forward_to(&derived, |data, derived| derived.emit(data));
+        other.forward_to(&derived, |data, derived| derived.emit(data));
+        derived
+    }
+
+    /// Returns an event emitter that emits the last value of this event
+    /// emitter once no value has been emitted for `duration`.
+    /// 
+    /// This is useful for reacting to bursts of events only once, such as
+    /// searching as the user types.
+    /// 
+    /// ```ignore
+    /// search_field.on_change().debounce(Duration::from_millis(300)).listener(|text| {
+    ///     // Search
+    /// });
+    /// ```
+    pub fn debounce(&self, duration: Duration) -> EventEmitter<T> {
+        let derived = EventEmitter::new();
+        let pending: Arc<Mutex<Option<FreeTimeout>>> = Arc::new(Mutex::new(None));
+        self.forward_to(&derived, move |data, derived| {
+            let mut pending = pending.lock().unwrap();
+            if let Some(timeout) = pending.take() {
+                timeout.stop();
+            }
+            let derived = Arc::downgrade(&derived.listener_list);
+            *pending = Some(timer::free_timeout(Box::new(move || {
+                if let Some(listener_list) = derived.upgrade() {
+                    EventEmitter { listener_list }.emit(data.clone());
+                }
+            }), duration));
+        });
+        derived
+    }
+
+    /// Returns an event emitter that emits a value of this event emitter,
+    /// then ignores further values until `duration` has elapsed.
+    /// 
+    /// This is useful for limiting the rate of frequent events, such as resizing.
+    pub fn throttle(&self, duration: Duration) -> EventEmitter<T> {
+        let derived = EventEmitter::new();
+        let last_emission: Mutex<Option<Instant>> = Mutex::new(None);
+        self.forward_to(&derived, move |data, derived| {
+            let now = Instant::now();
+            {
+                let mut last_emission = last_emission.lock().unwrap();
+                if last_emission.map(|last| now.since(last) < duration).unwrap_or(false) {
+                    return;
+                }
+                *last_emission = Some(now);
+            }
+            derived.emit(data);
+        });
+        derived
+    }
+
+    /// Adds a listener to this event emitter that forwards values
+    /// to `derived` while it is alive.
+    fn forward_to<U, F>(&self, derived: &EventEmitter<U>, forward: F)
+        where
+            U: Clone + Send + Sync + 'static,
+            F: Fn(T, &EventEmitter<U>) + Send + Sync + 'static
+    {
+        let derived: Weak<_> = Arc::downgrade(&derived.listener_list);
+        let derived_2 = derived.clone();
+        let listener = EventListener::with_options(
+            Arc::clone(&self.listener_list),
+            Box::new(move |data| {
+                if let Some(listener_list) = derived.upgrade() {
+                    forward(data, &EventEmitter { listener_list });
+                }
+            }),
+            0,
+            false,
+            Some(Box::new(move || derived_2.strong_count() != 0)),
+        );
+        listener.add();
+    }
+}
+
+impl<T: Clone + PartialEq + Send + Sync + 'static> EventEmitter<T> {
+    /// Returns an event emitter that emits the values of this event
+    /// emitter that differ from the previously emitted value.
+    pub fn distinct_until_changed(&self) -> EventEmitter<T> {
+        let derived = EventEmitter::new();
+        let last: Mutex<Option<T>> = Mutex::new(None);
+        self.forward_to(&derived, move |data, derived| {
+            {
+                let mut last = last.lock().unwrap();
+                if last.as_ref() == Some(&data) {
+                    return;
+                }
+                *last = Some(data.clone());
+            }
+            derived.emit(data);
+        });
+        derived
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use crate::{common::*, timer::Duration};
+
+    fn collect<T: Clone + Send + Sync + 'static>(emitter: &EventEmitter<T>) -> Arc<RwLock<Vec<T>>> {
+        let values = Arc::new(RwLock::new(vec![]));
+        emitter.listener({
+            let values = Arc::clone(&values);
+            move |data| values.write().unwrap().push(data)
+        });
+        values
+    }
+
+    #[test]
+    fn test_combinators() {
+        let source = EventEmitter::<u32>::new();
+        let other = EventEmitter::<u32>::new();
+
+        let mapped = collect(&source.filter(|data| data % 2 == 0).map(|data| data.to_string()));
+        let merged = collect(&source.merge(&other));
+        let distinct = collect(&source.distinct_until_changed());
+        let throttled = collect(&source.throttle(Duration::from_secs(3600)));
+        for data in [1, 2, 2, 4] {
+            source.emit(data);
+        }
+        other.emit(10);
+
+        assert_eq!(*mapped.read().unwrap(), ["2", "2", "4"]);
+        assert_eq!(*merged.read().unwrap(), [1, 2, 2, 4, 10]);
+        assert_eq!(*distinct.read().unwrap(), [1, 2, 4]);
+        assert_eq!(*throttled.read().unwrap(), [1]);
+
+        // Dropped derived emitters without listeners stop forwarding.
+        let count = source.listener_count();
+        drop(source.map(|data| data + 1));
+        source.emit(0);
+        assert_eq!(source.listener_count(), count);
+    }
+}
@@ -1,16 +1,46 @@
-use std::sync::{Arc, RwLock};
-use crate::common::*;
+use std::{cell::Cell, panic::{catch_unwind, AssertUnwindSafe}, sync::{Arc, PoisonError, RwLock}};
+use crate::{application::{self, ApplicationError}, common::*, util::inheritance::Node};
 
 type EventListenerList<T> = Arc<RwLock<Vec<EventListener<T>>>>;
 
+/// Maximum number of nested emissions on a thread, across all event emitters.
+/// Emissions beyond this depth are skipped and reported through `application::on_error()`.
+pub const MAX_EMIT_DEPTH: usize = 64;
+
+thread_local! {
+    static EMIT_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Restores the emission depth when an emission finishes.
+struct EmitDepthGuard;
+
+impl Drop for EmitDepthGuard {
+    fn drop(&mut self) {
+        EMIT_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
 /// An event emitter.
 ///
 /// `EventEmitter` consists of a sequence of listeners whose function is invoked
 /// when an event is emitted with a single data value.
 /// 
 /// Event listeners to an event emitter are created through the `listener` method.
+/// 
+/// # Dispatch semantics
+/// 
+/// * Listeners are invoked in priority order, then in the order they were added.
+/// * The listeners of an emission are those attached when the emission starts.
+///   Listeners added during an emission are first invoked by the next emission;
+///   listeners removed during an emission are not invoked if not reached yet.
+/// * Emitting from a listener runs the nested emission to completion before
+///   the remaining listeners of the outer emission are invoked.
+/// * A panicking listener does not prevent the remaining listeners from being invoked.
+///   The panic is reported through `application::on_error()`.
+/// * Nested emissions deeper than `MAX_EMIT_DEPTH` are skipped and reported
+///   through `application::on_error()`.
 pub struct EventEmitter<T: Clone> {
-    listener_list: EventListenerList<T>,
+    pub(super) listener_list: EventListenerList<T>,
 }
 
 impl<T: Clone> EventEmitter<T> {
@@ -20,23 +50,113 @@ impl<T: Clone> EventEmitter<T> {
         }
     }
 
-    /// Adds a listener to an event emitter.
+    /// Adds a listener to an event emitter, with the default priority of zero.
     pub fn listener<F>(&self, function: F) -> EventListener<T>
         where F: Fn(T) + Send + Sync + 'static
     {
-        let listener = EventListener::new(Arc::clone(&self.listener_list), function);
+        self.listener_with(0, function)
+    }
+
+    /// Adds a listener to an event emitter with a priority. Listeners
+    /// with higher priority are invoked first; listeners of equal
+    /// priority are invoked in the order they were added.
+    pub fn listener_with<F>(&self, priority: i32, function: F) -> EventListener<T>
+        where F: Fn(T) + Send + Sync + 'static
+    {
+        let listener = EventListener::with_options(Arc::clone(&self.listener_list), Box::new(function), priority, false, None);
         listener.add();
         listener
     }
 
+    /// Adds a listener that is removed before it is invoked
+    /// for the first time.
+    pub fn once<F>(&self, function: F) -> EventListener<T>
+        where F: Fn(T) + Send + Sync + 'static
+    {
+        let listener = EventListener::with_options(Arc::clone(&self.listener_list), Box::new(function), 0, true, None);
+        listener.add();
+        listener
+    }
+
+    /// Adds a listener that holds a weak reference to `owner`, such as a display object,
+    /// instead of capturing it. The listener receives the owner as its first argument.
+    /// 
+    /// Once the owner is dropped, the listener is removed, along with its function,
+    /// by the next emission or the next listener added to the event emitter.
+    /// 
+    /// ```ignore
+    /// button.on_click().weak_listener(&label, |label, _| {
+    ///     label.set_text("Clicked".into());
+    /// });
+    /// ```
+    pub fn weak_listener<O, F>(&self, owner: &O, function: F) -> EventListener<T>
+        where
+            O: AsRef<Node> + TryFrom<Node>,
+            F: Fn(O, T) + Send + Sync + 'static
+    {
+        let owner = owner.as_ref().downgrade_ref();
+        let owner_2 = owner.clone();
+        let listener = EventListener::with_options(
+            Arc::clone(&self.listener_list),
+            Box::new(move |data| {
+                if let Some(Ok(owner)) = owner.upgrade().map(O::try_from) {
+                    function(owner, data);
+                }
+            }),
+            0,
+            false,
+            Some(Box::new(move || owner_2.upgrade().is_some())),
+        );
+        listener.add();
+        listener
+    }
+
+    /// The number of attached listeners.
+    pub fn listener_count(&self) -> usize {
+        self.listener_list.read().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
     /// Emits a single data value.
     pub fn emit(&self, data: T) {
-        let mut list_2 = vec![];
-        for listener in &*self.listener_list.read().unwrap() {
-            list_2.push(listener.clone());
+        self.emit_until(data, || false);
+    }
+
+    /// Emits a single data value, stopping before the next
+    /// listener once `stop` returns `true`.
+    pub(crate) fn emit_until(&self, data: T, stop: impl Fn() -> bool) {
+        self.emit_with_limit(data, stop, true);
+    }
+
+    /// Emits a single data value regardless of the emission depth.
+    /// This is used for reporting errors at the depth limit.
+    pub(crate) fn emit_unlimited(&self, data: T) {
+        self.emit_with_limit(data, || false, false);
+    }
+
+    fn emit_with_limit(&self, data: T, stop: impl Fn() -> bool, limited: bool) {
+        let depth = EMIT_DEPTH.with(|depth| depth.get());
+        if limited && depth >= MAX_EMIT_DEPTH {
+            application::report_error(ApplicationError::EmitRecursionLimit { depth: MAX_EMIT_DEPTH });
+            return;
         }
+        EMIT_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        let _depth_guard = EmitDepthGuard;
+
+        let list_2 = self.listener_list.read().unwrap_or_else(PoisonError::into_inner).clone();
         for listener in list_2 {
-            (listener.inner.function)(data.clone());
+            if stop() {
+                break;
+            }
+            if !listener.is_active() {
+                continue;
+            }
+            let data = data.clone();
+            if let Err(panic) = catch_unwind(AssertUnwindSafe(|| listener.invoke(data))) {
+                let message = panic.downcast_ref::<&str>().map(|message| message.to_string())
+                    .or(panic.downcast_ref::<String>().cloned())
+                    .unwrap_or("Unknown panic".into());
+                application::report_error(ApplicationError::ListenerPanic { message });
+            }
         }
     }
 }
@@ -83,38 +203,241 @@ impl<T: Clone> Clone for EventListener<T> {
 
 impl<T: Clone> EventListener<T> {
     pub fn new<F: Fn(T) + Send + Sync + 'static>(listener_list: EventListenerList<T>, function: F) -> Self {
+        Self::with_options(listener_list, Box::new(function), 0, false, None)
+    }
+
+    pub(super) fn with_options(listener_list: EventListenerList<T>, function: Box<dyn Fn(T) + Send + Sync + 'static>, priority: i32, once: bool, alive: Option<Box<dyn Fn() -> bool + Send + Sync + 'static>>) -> Self {
         Self {
             inner: Arc::new(EventListenerInner {
                 listener_list,
-                function: Box::new(function),
+                function,
+                priority,
+                once,
+                alive,
             }),
         }
     }
 
-    /// Adds the event listener to the end of the sequence of listeners if it was previously
-    /// removed by the `remove` method. If the event listener is already attached
-    /// to the sequence, it is moved to the end of the sequence.
+    /// The priority of the event listener.
+    pub fn priority(&self) -> i32 {
+        self.inner.priority
+    }
+
+    /// Adds the event listener after the listeners of the same or higher priority
+    /// if it was previously removed by the `remove` method. If the event listener
+    /// is already attached to the sequence, it is moved after them.
+    /// 
+    /// Weak listeners whose owner was dropped are removed from the sequence.
     pub fn add(&self) {
         self.remove();
-        let list = &self.inner.listener_list;
-        list.write().unwrap().push(self.clone());
+        let mut list = self.inner.listener_list.write().unwrap_or_else(PoisonError::into_inner);
+        list.retain(|listener| listener.inner.alive.as_ref().is_none_or(|alive| alive()));
+        let index = list.iter().position(|listener| listener.inner.priority < self.inner.priority).unwrap_or(list.len());
+        list.insert(index, self.clone());
     }
 
     /// Indicates whether the event listener is attached to the sequence of listeners,
     /// that is, whether it was not removed from the sequence.
     pub fn is_active(&self) -> bool {
         let list = &self.inner.listener_list;
-        list.read().unwrap().contains(self)
+        list.read().unwrap_or_else(PoisonError::into_inner).contains(self)
     }
 
-    /// Removes the event listener from the sequence of listeners.
-    pub fn remove(&self) {
+    /// Removes the event listener from the sequence of listeners. Returns `true`
+    /// if the event listener has been removed, or `false` if it was not attached.
+    pub fn remove(&self) -> bool {
         let list = &self.inner.listener_list;
-        list.write().unwrap().remove_equals(self);
+        list.write().unwrap_or_else(PoisonError::into_inner).remove_equals(self)
+    }
+
+    /// Returns a guard that removes the event listener when dropped.
+    /// 
+    /// ```ignore
+    /// let _guard = button.on_click().listener(|_| {
+    ///     // Action
+    /// }).guard();
+    /// ```
+    pub fn guard(self) -> EventListenerGuard<T> {
+        EventListenerGuard { listener: self }
+    }
+
+    fn invoke(&self, data: T) {
+        if let Some(alive) = self.inner.alive.as_ref() {
+            if !alive() {
+                self.remove();
+                return;
+            }
+        }
+        // Only the emission that removes a once-listener invokes it,
+        // even when emitting from several threads at once.
+        if self.inner.once && !self.remove() {
+            return;
+        }
+        (self.inner.function)(data);
     }
 }
 
 struct EventListenerInner<T: Clone> {
     listener_list: EventListenerList<T>,
     function: Box<dyn Fn(T) + Send + Sync + 'static>,
+    priority: i32,
+    once: bool,
+    /// Indicates whether the owner of a weak listener is still alive.
+    alive: Option<Box<dyn Fn() -> bool + Send + Sync + 'static>>,
+}
+
+/// Removes an event listener when dropped. Guards are created
+/// through the `EventListener::guard` method.
+pub struct EventListenerGuard<T: Clone> {
+    listener: EventListener<T>,
+}
+
+impl<T: Clone> EventListenerGuard<T> {
+    pub fn listener(&self) -> &EventListener<T> {
+        &self.listener
+    }
+}
+
+impl<T: Clone> Drop for EventListenerGuard<T> {
+    fn drop(&mut self) {
+        self.listener.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use crate::{application::ApplicationError, display::*, events::*};
+
+    #[test]
+    fn test_listener_options() {
+        let emitter = EventEmitter::<u32>::new();
+        let log = Arc::new(RwLock::new(Vec::<String>::new()));
+        let logger = |name: &'static str| {
+            let log = Arc::clone(&log);
+            move |data: u32| log.write().unwrap().push(format!("{name} {data}"))
+        };
+
+        emitter.listener(logger("a"));
+        emitter.listener_with(10, logger("high"));
+        emitter.listener_with(-1, logger("low"));
+        emitter.listener(logger("b"));
+        let once = emitter.once(logger("once"));
+        emitter.emit(1);
+        emitter.emit(2);
+        assert!(!once.is_active());
+        assert_eq!(log.read().unwrap()[..5], ["high 1", "a 1", "b 1", "once 1", "low 1"]);
+        assert_eq!(log.read().unwrap()[5..], ["high 2", "a 2", "b 2", "low 2"]);
+
+        log.write().unwrap().clear();
+        let guard = emitter.listener(logger("guarded")).guard();
+        let listener = guard.listener().clone();
+        drop(guard);
+        assert!(!listener.is_active());
+
+        let owner = DisplayObject::new();
+        let weak = emitter.weak_listener(&owner, {
+            let log = Arc::clone(&log);
+            move |owner: DisplayObject, data| log.write().unwrap().push(format!("weak {} {data}", owner.alpha()))
+        });
+        emitter.emit(3);
+        assert!(log.read().unwrap().contains(&"weak 1 3".to_owned()));
+        drop(owner);
+        // Adding a listener removes weak listeners whose owner was dropped.
+        emitter.listener(|_| {});
+        assert!(!weak.is_active());
+    }
+
+    #[test]
+    fn test_once_concurrent_emit() {
+        use std::sync::{atomic::{AtomicUsize, Ordering}, Barrier};
+        for _ in 0..50 {
+            let emitter = Arc::new(EventEmitter::<u32>::new());
+            let count = Arc::new(AtomicUsize::new(0));
+            emitter.once({
+                let count = Arc::clone(&count);
+                move |_| { count.fetch_add(1, Ordering::SeqCst); }
+            });
+            let barrier = Arc::new(Barrier::new(4));
+            let threads: Vec<_> = (0..4).map(|_| {
+                let (emitter, barrier) = (Arc::clone(&emitter), Arc::clone(&barrier));
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    emitter.emit(0);
+                })
+            }).collect();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn test_dispatch_semantics() {
+        let emitter = Arc::new(EventEmitter::<u32>::new());
+        let log = Arc::new(RwLock::new(Vec::<String>::new()));
+
+        // Listeners added during an emission are not invoked by it,
+        // and removed listeners that were not reached are skipped.
+        let second: Arc<RwLock<Option<EventListener<u32>>>> = Arc::new(RwLock::new(None));
+        let first = emitter.listener({
+            let (emitter, log, second) = (Arc::clone(&emitter), Arc::clone(&log), Arc::clone(&second));
+            move |data| {
+                log.write().unwrap().push(format!("first {data}"));
+                if data == 1 {
+                    second.read().unwrap().as_ref().unwrap().remove();
+                    emitter.listener({
+                        let log = Arc::clone(&log);
+                        move |data| log.write().unwrap().push(format!("added {data}"))
+                    });
+                    emitter.emit(2);
+                }
+            }
+        });
+        *second.write().unwrap() = Some(emitter.listener({
+            let log = Arc::clone(&log);
+            move |data| log.write().unwrap().push(format!("second {data}"))
+        }));
+        emitter.emit(1);
+        assert_eq!(*log.read().unwrap(), ["first 1", "first 2", "added 2"]);
+        first.remove();
+
+        // Panicking listeners are reported and do not stop the emission.
+        // Errors are reported on the thread that raised them, so that
+        // errors raised by other tests running in parallel are ignored.
+        let errors = Arc::new(RwLock::new(vec![]));
+        let _error_listener = crate::application::on_error().listener({
+            let (errors, thread) = (Arc::clone(&errors), std::thread::current().id());
+            move |error| if std::thread::current().id() == thread {
+                errors.write().unwrap().push(error);
+            }
+        }).guard();
+        let emitter_2 = EventEmitter::<u32>::new();
+        emitter_2.listener(|_| panic!("buggy plugin in test_dispatch_semantics"));
+        emitter_2.listener({
+            let log = Arc::clone(&log);
+            move |data| log.write().unwrap().push(format!("after panic {data}"))
+        });
+        emitter_2.emit(3);
+        assert_eq!(log.read().unwrap().last().map(|entry| entry.as_str()), Some("after panic 3"));
+        assert!(errors.read().unwrap().contains(&ApplicationError::ListenerPanic { message: "buggy plugin in test_dispatch_semantics".into() }));
+
+        // Infinitely recursive emission stops at the depth limit.
+        let emitter_3 = Arc::new(EventEmitter::<u32>::new());
+        let count = Arc::new(RwLock::new(0));
+        emitter_3.listener({
+            let (emitter_3, count) = (Arc::clone(&emitter_3), Arc::clone(&count));
+            move |data| {
+                *count.write().unwrap() += 1;
+                emitter_3.emit(data);
+            }
+        });
+        emitter_3.emit(0);
+        assert_eq!(*count.read().unwrap(), MAX_EMIT_DEPTH);
+        assert_eq!(*errors.read().unwrap(), [
+            ApplicationError::ListenerPanic { message: "buggy plugin in test_dispatch_semantics".into() },
+            ApplicationError::EmitRecursionLimit { depth: MAX_EMIT_DEPTH },
+        ]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use std::{collections::VecDeque, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll, Waker}};
+use futures::{Future, Stream, StreamExt};
+use crate::common::*;
+
+/// Buffer capacity of streams created by `EventEmitter::stream()`.
+pub const DEFAULT_EVENT_STREAM_CAPACITY: usize = 64;
+
+/// Determines which value is discarded when an event arrives
+/// while the buffer of an `EventStream` is full.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OverflowPolicy {
+    /// Discards the oldest buffered value, keeping the new one.
+    DropOldest,
+    /// Discards the new value, keeping the buffered ones.
+    DropNewest,
+}
+
+impl<T: Clone + Send + 'static> EventEmitter<T> {
+    /// Returns a future that resolves to the next emitted value.
+    /// The value is captured from the moment this method is called,
+    /// not from the moment the future is first polled.
+    /// 
+    /// ```ignore
+    /// let click = button.on_click().next().await;
+    /// ```
+    pub fn next(&self) -> impl Future<Output = T> + Unpin {
+        self.stream_with_capacity(1, OverflowPolicy::DropNewest).map_next()
+    }
+
+    /// Returns a stream of the emitted values, buffering up to
+    /// `DEFAULT_EVENT_STREAM_CAPACITY` values and dropping the
+    /// oldest ones on overflow.
+    /// 
+    /// ```ignore
+    /// let mut clicks = button.on_click().stream();
+    /// while let Some(click) = clicks.next().await {
+    ///     // Action
+    /// }
+    /// ```
+    pub fn stream(&self) -> EventStream<T> {
+        self.stream_with_capacity(DEFAULT_EVENT_STREAM_CAPACITY, OverflowPolicy::DropOldest)
+    }
+
+    /// Returns a stream of the emitted values, buffering up to `capacity`
+    /// values and applying `policy` on overflow.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `capacity` is zero.
+    pub fn stream_with_capacity(&self, capacity: usize, policy: OverflowPolicy) -> EventStream<T> {
+        assert!(capacity != 0, "Event stream capacity must be greater than zero");
+        let state = Arc::new(Mutex::new(EventStreamState {
+            buffer: VecDeque::new(),
+            waker: None,
+        }));
+        let listener = self.listener({
+            let state = Arc::clone(&state);
+            move |data| {
+                let mut state = state.lock().unwrap();
+                if state.buffer.len() == capacity {
+                    match policy {
+                        OverflowPolicy::DropOldest => { state.buffer.pop_front(); },
+                        OverflowPolicy::DropNewest => return,
+                    }
+                }
+                state.buffer.push_back(data);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+        EventStream { state, listener }
+    }
+}
+
+/// A stream of the values emitted by an `EventEmitter`.
+/// 
+/// The stream never ends by itself. Dropping the stream removes
+/// its listener from the event emitter.
+pub struct EventStream<T: Clone> {
+    state: Arc<Mutex<EventStreamState<T>>>,
+    listener: EventListener<T>,
+}
+
+struct EventStreamState<T> {
+    buffer: VecDeque<T>,
+    waker: Option<Waker>,
+}
+
+impl<T: Clone> EventStream<T> {
+    fn map_next(mut self) -> impl Future<Output = T> + Unpin {
+        futures::future::poll_fn(move |cx| self.poll_next_unpin(cx).map(|data| data.unwrap()))
+    }
+}
+
+impl<T: Clone> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(data) = state.buffer.pop_front() {
+            return Poll::Ready(Some(data));
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T: Clone> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        self.listener.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, StreamExt};
+    use crate::events::*;
+
+    #[test]
+    fn test_event_stream() {
+        let emitter = EventEmitter::<u32>::new();
+
+        let next = emitter.next();
+        emitter.emit(1);
+        emitter.emit(2);
+        assert_eq!(block_on(next), 1);
+
+        let mut stream = emitter.stream_with_capacity(2, OverflowPolicy::DropOldest);
+        for i in 1..=3 {
+            emitter.emit(i);
+        }
+        assert_eq!(block_on(stream.next()), Some(2));
+        assert_eq!(block_on(stream.next()), Some(3));
+
+        let mut stream_2 = emitter.stream_with_capacity(2, OverflowPolicy::DropNewest);
+        for i in 1..=3 {
+            emitter.emit(i);
+        }
+        assert_eq!(block_on(stream_2.next()), Some(1));
+        assert_eq!(block_on(stream_2.next()), Some(2));
+
+        let listener = stream.listener.clone();
+        drop(stream);
+        assert!(!listener.is_active());
+    }
+}
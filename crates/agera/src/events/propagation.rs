@@ -0,0 +1,321 @@
+use std::sync::{Arc, RwLock};
+use crate::{common::*, util::inheritance::{Node, WeakNodeRef}};
+
+/// The phase of a propagating event.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EventPhase {
+    /// The event travels from the root node down to the parent of the target.
+    Capturing,
+    /// The event is at its target.
+    AtTarget,
+    /// The event travels from the parent of the target up to the root node.
+    Bubbling,
+}
+
+/// An event propagating through the node graph.
+///
+/// A propagating event travels in three phases: it is first delivered to
+/// capture listeners from the root node down to the parent of the target,
+/// then to the listeners of the target itself, and then to the non-capture
+/// listeners from the parent of the target up to the root node.
+///
+/// Cloning an `Event` clones a reference to the same event, thus calling
+/// `stop_propagation()` on a clone stops the original event.
+pub struct Event<T> {
+    inner: Arc<EventInner<T>>,
+}
+
+struct EventInner<T> {
+    data: T,
+    target: Node,
+    current_target: RwLock<Node>,
+    phase: RwLock<EventPhase>,
+    propagation_stopped: RwLock<bool>,
+    immediate_propagation_stopped: RwLock<bool>,
+    default_prevented: RwLock<bool>,
+}
+
+impl<T> Clone for Event<T> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<T> Event<T> {
+    fn new(data: T, target: Node) -> Self {
+        Self {
+            inner: Arc::new(EventInner {
+                data,
+                current_target: RwLock::new(target.clone()),
+                target,
+                phase: RwLock::new(EventPhase::AtTarget),
+                propagation_stopped: RwLock::new(false),
+                immediate_propagation_stopped: RwLock::new(false),
+                default_prevented: RwLock::new(false),
+            }),
+        }
+    }
+
+    /// The data carried by the event.
+    pub fn data(&self) -> &T {
+        &self.inner.data
+    }
+
+    /// The node the event was dispatched to.
+    pub fn target(&self) -> Node {
+        self.inner.target.clone()
+    }
+
+    /// The node whose listeners are currently being invoked.
+    pub fn current_target(&self) -> Node {
+        self.inner.current_target.read().unwrap().clone()
+    }
+
+    pub fn phase(&self) -> EventPhase {
+        *self.inner.phase.read().unwrap()
+    }
+
+    /// Stops the event from reaching further nodes. The remaining
+    /// listeners of the current node are still invoked.
+    pub fn stop_propagation(&self) {
+        *self.inner.propagation_stopped.write().unwrap() = true;
+    }
+
+    /// Stops the event from reaching any further listener,
+    /// including the remaining listeners of the current node.
+    pub fn stop_immediate_propagation(&self) {
+        *self.inner.propagation_stopped.write().unwrap() = true;
+        *self.inner.immediate_propagation_stopped.write().unwrap() = true;
+    }
+
+    pub fn is_propagation_stopped(&self) -> bool {
+        *self.inner.propagation_stopped.read().unwrap()
+    }
+
+    /// Indicates that the default action of the event should not be taken.
+    /// The dispatcher reads this through the result of `dispatch_event()`.
+    pub fn prevent_default(&self) {
+        *self.inner.default_prevented.write().unwrap() = true;
+    }
+
+    pub fn is_default_prevented(&self) -> bool {
+        *self.inner.default_prevented.read().unwrap()
+    }
+
+    fn is_immediate_propagation_stopped(&self) -> bool {
+        *self.inner.immediate_propagation_stopped.read().unwrap()
+    }
+}
+
+/// Listeners of a node for events carrying `T`, stored as a node component.
+struct NodeEventListeners<T> {
+    capture: EventEmitter<Event<T>>,
+    bubble: EventEmitter<Event<T>>,
+}
+
+/// Selects the capture or bubble emitter of `NodeEventListeners`.
+type PhaseEmitter<T> = fn(&NodeEventListeners<T>) -> &EventEmitter<Event<T>>;
+
+fn node_event_listeners<T>(node: &Node) -> Arc<NodeEventListeners<T>>
+    where T: Send + Sync + 'static
+{
+    if let Some(listeners) = node.get::<NodeEventListeners<T>>() {
+        return listeners;
+    }
+    node.set(NodeEventListeners::<T> {
+        capture: EventEmitter::new(),
+        bubble: EventEmitter::new(),
+    });
+    node.get::<NodeEventListeners<T>>().unwrap()
+}
+
+/// Adds a listener for events carrying `T` to a node, either
+/// in the capturing phase or in the target and bubbling phases.
+pub(crate) fn add_event_listener<T, F>(node: &Node, capture: bool, function: F) -> EventListener<Event<T>>
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Event<T>) + Send + Sync + 'static
+{
+    let listeners = node_event_listeners::<T>(node);
+    if capture { listeners.capture.listener(function) } else { listeners.bubble.listener(function) }
+}
+
+/// Dispatches an event carrying `data` to a node, propagating it
+/// through its ancestors. Returns `false` if any listener
+/// called `prevent_default()`; otherwise returns `true`.
+pub(crate) fn dispatch_event<T>(target: &Node, data: T) -> bool
+    where T: Send + Sync + 'static
+{
+    let event = Event::new(data, target.clone());
+    let mut ancestors = vec![];
+    let mut node = target.parent();
+    while let Some(parent) = node {
+        node = parent.parent();
+        ancestors.push(parent);
+    }
+
+    let deliver = |node: &Node, phase: EventPhase, emitters: &[PhaseEmitter<T>]| {
+        let Some(listeners) = node.get::<NodeEventListeners<T>>() else {
+            return;
+        };
+        *event.inner.current_target.write().unwrap() = node.clone();
+        *event.inner.phase.write().unwrap() = phase;
+        for emitter in emitters {
+            emitter(&listeners).emit_until(event.clone(), || event.is_immediate_propagation_stopped());
+        }
+    };
+
+    for node in ancestors.iter().rev() {
+        if event.is_propagation_stopped() {
+            break;
+        }
+        deliver(node, EventPhase::Capturing, &[|listeners| &listeners.capture]);
+    }
+    if !event.is_propagation_stopped() {
+        deliver(target, EventPhase::AtTarget, &[|listeners| &listeners.capture, |listeners| &listeners.bubble]);
+    }
+    for node in ancestors.iter() {
+        if event.is_propagation_stopped() {
+            break;
+        }
+        deliver(node, EventPhase::Bubbling, &[|listeners| &listeners.bubble]);
+    }
+    !event.is_default_prevented()
+}
+
+/// Listeners of a node for its removal from the tree, stored as a node component,
+/// along with the root of the tree the node was in when last checked.
+struct RemovedFromTreeListeners {
+    emitter: Arc<EventEmitter<()>>,
+    root: RwLock<WeakNodeRef>,
+}
+
+/// Nodes with listeners for their removal from the tree.
+static TRACKED_NODES: Lazy<RwLock<Vec<WeakNodeRef>>> = Lazy::new(|| RwLock::new(vec![]));
+
+/// Returns an event emitter that emits when a node leaves its tree,
+/// either by being removed from its parent or by having an ancestor removed
+/// from its parent. Moving a node within the same tree does not emit.
+/// Display objects expose it as `on_removed_from_tree()`.
+/// 
+/// Removals are detected by [`check_removed_from_tree()`], which the frame
+/// loop of the application calls once per frame, regardless of whether
+/// the node was removed through a `Node` or a display object.
+pub fn on_removed_from_tree(node: &Node) -> Arc<EventEmitter<()>> {
+    if let Some(listeners) = node.get::<RemovedFromTreeListeners>() {
+        // Notify the current listeners of a pending removal, so that
+        // new listeners observe the tree the node is in now.
+        check_node_removed_from_tree(node);
+        return Arc::clone(&listeners.emitter);
+    }
+    node.set(RemovedFromTreeListeners {
+        emitter: Arc::new(EventEmitter::new()),
+        root: RwLock::new(root_of(node).downgrade_ref()),
+    });
+    TRACKED_NODES.write().unwrap().push(node.downgrade_ref());
+    Arc::clone(&node.get::<RemovedFromTreeListeners>().unwrap().emitter)
+}
+
+/// Emits `on_removed_from_tree()` to the nodes that left their tree
+/// since they were last checked.
+pub fn check_removed_from_tree() {
+    let nodes: Vec<Node> = {
+        let mut tracked = TRACKED_NODES.write().unwrap();
+        tracked.retain(|node| node.upgrade().is_some());
+        tracked.iter().filter_map(WeakNodeRef::upgrade).collect()
+    };
+    for node in nodes {
+        check_node_removed_from_tree(&node);
+    }
+}
+
+/// Emits `on_removed_from_tree()` to a node if it left
+/// its tree since it was last checked.
+pub(crate) fn check_node_removed_from_tree(node: &Node) {
+    let Some(listeners) = node.get::<RemovedFromTreeListeners>() else {
+        return;
+    };
+    let removed = {
+        let mut root = listeners.root.write().unwrap();
+        let removed = !root.upgrade().is_some_and(|root| is_ancestor_or_self(&root, node));
+        *root = root_of(node).downgrade_ref();
+        removed
+    };
+    if removed {
+        listeners.emitter.emit(());
+    }
+}
+
+fn is_ancestor_or_self(ancestor: &Node, node: &Node) -> bool {
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        if node == *ancestor {
+            return true;
+        }
+        current = node.parent();
+    }
+    false
+}
+
+fn root_of(node: &Node) -> Node {
+    let mut root = node.clone();
+    while let Some(parent) = root.parent() {
+        root = parent;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use crate::{display::*, events::*, util::inheritance::*};
+
+    #[derive(Clone)]
+    struct Click;
+
+    #[test]
+    fn test_propagation() {
+        let root = DisplayObject::new();
+        let container = DisplayObject::new();
+        let button = DisplayObject::new();
+        root.add_child(&container);
+        container.add_child(&button);
+
+        let log = Arc::new(RwLock::new(Vec::<String>::new()));
+        let logger = |name: &'static str| {
+            let log = Arc::clone(&log);
+            move |e: Event<Click>| log.write().unwrap().push(format!("{name} {:?}", e.phase()))
+        };
+        root.add_capture_listener(logger("root"));
+        container.add_event_listener(logger("container"));
+        button.add_event_listener(logger("button"));
+        button.add_capture_listener(logger("button capture"));
+        root.add_event_listener(logger("root"));
+
+        assert!(button.dispatch_event(Click));
+        assert_eq!(*log.read().unwrap(), [
+            "root Capturing",
+            "button capture AtTarget",
+            "button AtTarget",
+            "container Bubbling",
+            "root Bubbling",
+        ]);
+
+        log.write().unwrap().clear();
+        let button_node: Node = (*button).clone();
+        let stopper = container.add_event_listener(move |e: Event<Click>| {
+            assert!(e.target() == button_node && e.current_target() != button_node);
+            e.prevent_default();
+            e.stop_propagation();
+        });
+        assert!(!button.dispatch_event(Click));
+        assert_eq!(log.read().unwrap().last().map(|entry| entry.as_str()), Some("container Bubbling"));
+        stopper.remove();
+
+        log.write().unwrap().clear();
+        button.add_capture_listener(|e: Event<Click>| e.stop_immediate_propagation());
+        button.add_capture_listener(logger("unreachable"));
+        button.dispatch_event(Click);
+        assert_eq!(*log.read().unwrap(), ["root Capturing", "button capture AtTarget"]);
+    }
+}
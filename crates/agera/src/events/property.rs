@@ -0,0 +1,115 @@
+use std::{any::Any, sync::{Arc, RwLock}};
+use crate::{common::*, util::inheritance::Node};
+
+/// A change to an observable property, emitted by the `on_<name>_changed()`
+/// event emitters generated through [`observable_properties!`](crate::events::observable_properties).
+#[derive(Clone, Debug)]
+pub struct PropertyChange<T> {
+    /// The property name.
+    pub name: &'static str,
+    pub old_value: T,
+    pub new_value: T,
+}
+
+/// A change to an observable property of any type, emitted
+/// by `on_property_changed()`.
+#[derive(Clone)]
+pub struct AnyPropertyChange {
+    /// The property name.
+    pub name: &'static str,
+    old_value: Arc<dyn Any + Send + Sync>,
+    new_value: Arc<dyn Any + Send + Sync>,
+}
+
+impl AnyPropertyChange {
+    /// The old value, if the property is of type `T`.
+    pub fn old_value<T: 'static>(&self) -> Option<&T> {
+        self.old_value.downcast_ref::<T>()
+    }
+
+    /// The new value, if the property is of type `T`.
+    pub fn new_value<T: 'static>(&self) -> Option<&T> {
+        self.new_value.downcast_ref::<T>()
+    }
+}
+
+/// Event emitters of the observable properties of a node,
+/// stored as a node component.
+struct PropertyEmitters {
+    any: Arc<EventEmitter<AnyPropertyChange>>,
+    by_name: RwLock<HashMap<&'static str, Arc<dyn Any + Send + Sync>>>,
+}
+
+fn property_emitters(node: &Node) -> Arc<PropertyEmitters> {
+    if let Some(emitters) = node.get::<PropertyEmitters>() {
+        return emitters;
+    }
+    node.set(PropertyEmitters {
+        any: Arc::new(EventEmitter::new()),
+        by_name: RwLock::new(HashMap::new()),
+    });
+    node.get::<PropertyEmitters>().unwrap()
+}
+
+/// Returns an event emitter that emits changes to any observable property
+/// of a node. Display objects expose it as `on_property_changed()`.
+pub fn on_property_changed(node: &Node) -> Arc<EventEmitter<AnyPropertyChange>> {
+    Arc::clone(&property_emitters(node).any)
+}
+
+/// *Internal function used by `observable_properties!`.*
+#[doc(hidden)]
+pub fn property_emitter<T>(node: &Node, name: &'static str) -> Arc<EventEmitter<PropertyChange<T>>>
+    where T: Clone + Send + Sync + 'static
+{
+    let emitters = property_emitters(node);
+    let mut by_name = emitters.by_name.write().unwrap();
+    let emitter = by_name.entry(name).or_insert_with(|| Arc::new(EventEmitter::<PropertyChange<T>>::new()));
+    Arc::clone(emitter).downcast::<EventEmitter<PropertyChange<T>>>().expect("Property emitter type mismatch")
+}
+
+/// *Internal function used by `observable_properties!`.*
+#[doc(hidden)]
+pub fn notify_property_change<T>(node: &Node, name: &'static str, old_value: T, new_value: T)
+    where T: Clone + Send + Sync + 'static
+{
+    // Nodes whose properties were never observed have no emitters.
+    let Some(emitters) = node.get::<PropertyEmitters>() else {
+        return;
+    };
+    let emitter = emitters.by_name.read().unwrap().get(name).cloned();
+    if let Some(emitter) = emitter.and_then(|emitter| emitter.downcast::<EventEmitter<PropertyChange<T>>>().ok()) {
+        emitter.emit(PropertyChange { name, old_value: old_value.clone(), new_value: new_value.clone() });
+    }
+    emitters.any.emit(AnyPropertyChange {
+        name,
+        old_value: Arc::new(old_value),
+        new_value: Arc::new(new_value),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use crate::{display::*, geom::*};
+
+    #[test]
+    fn test_observable_properties() {
+        let display_object = DisplayObject::new();
+        let changes = Arc::new(RwLock::new(vec![]));
+        display_object.on_alpha_changed().listener({
+            let changes = Arc::clone(&changes);
+            move |change| changes.write().unwrap().push((change.old_value, change.new_value))
+        });
+        let names = Arc::new(RwLock::new(vec![]));
+        display_object.on_property_changed().listener({
+            let names = Arc::clone(&names);
+            move |change| names.write().unwrap().push((change.name, change.new_value::<Vector2d>().cloned()))
+        });
+
+        display_object.set_alpha(0.5).set_alpha(0.5).set_position(Vector2d(1.0, 2.0));
+        assert_eq!(display_object.alpha(), 0.5);
+        assert_eq!(*changes.read().unwrap(), [(1.0, 0.5)]);
+        assert_eq!(*names.read().unwrap(), [("alpha", None), ("position", Some(Vector2d(1.0, 2.0)))]);
+    }
+}
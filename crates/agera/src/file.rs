@@ -10,6 +10,18 @@ use std::path::Path;
 
 pub(crate) mod platforms;
 
+mod stream;
+pub use stream::*;
+
+mod atomic;
+pub use atomic::*;
+
+mod watch;
+pub use watch::*;
+
+mod walk;
+pub use walk::*;
+
 /// Represents a path to a file or directory, either in the native file system, application or
 /// application storage directory.
 /// 
@@ -324,7 +336,7 @@ impl File {
     /// 
     pub fn is_symbolic_link(&self) -> bool {
         if_native_platform! {{
-            std::fs::metadata(&self.path_omega()).map(|data| data.is_symlink()).unwrap_or(false)
+            std::fs::symlink_metadata(self.path_omega()).map(|data| data.is_symlink()).unwrap_or(false)
         }}
         if_browser! {{
             unsupported_browser_sync_operation!();
@@ -334,7 +346,7 @@ impl File {
     /// Indicates whether the `File` object is a symbolic link, asynchronously.
     pub async fn is_symbolic_link_async(&self) -> bool {
         if_native_platform! {{
-            tokio::fs::metadata(&self.path_omega()).await.map(|data| data.is_symlink()).unwrap_or(false)
+            tokio::fs::symlink_metadata(&self.path_omega()).await.map(|data| data.is_symlink()).unwrap_or(false)
         }}
         if_browser! {{
             false
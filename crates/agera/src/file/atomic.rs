@@ -0,0 +1,206 @@
+use std::io;
+use crate::platforms::{if_native_platform, if_browser};
+use super::*;
+
+if_native_platform! {
+    use std::{io::Write, path::PathBuf, sync::atomic::{AtomicU64, Ordering}};
+}
+
+/// Options for `File::write_atomic()` and `File::write_atomic_async()`.
+///
+/// ```ignore
+/// File::new("app-storage://settings.json").write_atomic(json, AtomicWriteOptions { keep_backup: true, ..default() })?;
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AtomicWriteOptions {
+    /// Whether to wait for the data to reach the storage device
+    /// before and after replacing the file. Defaults to `true`.
+    pub sync: bool,
+    /// Whether to keep the previous contents of the file
+    /// in its backup file (see `File::backup_file()`). Defaults to `false`.
+    pub keep_backup: bool,
+}
+
+impl Default for AtomicWriteOptions {
+    fn default() -> Self {
+        Self { sync: true, keep_backup: false }
+    }
+}
+
+impl File {
+    /// The backup file kept by atomic writes, which is a sibling
+    /// whose name is the name of this file followed by `.bak`.
+    pub fn backup_file(&self) -> File {
+        self.parent().resolve_path(&format!("{}.bak", self.name()))
+    }
+
+    /// Replaces the contents of a file synchronously, such that the file
+    /// contains either its previous contents or `data` even if the
+    /// application crashes while writing. The data is written to a
+    /// temporary sibling file, which is then renamed over the file.
+    ///
+    /// The file keeps its permissions. If the file is a symbolic link,
+    /// the file it points to is replaced and the link is kept.
+    ///
+    /// # Browser support
+    ///
+    /// This is a synchronous operation, therefore it is not supported
+    /// in the browser.
+    ///
+    pub fn write_atomic<T: AsRef<[u8]>>(&self, data: T, options: AtomicWriteOptions) -> io::Result<()> {
+        if self.scheme == FileScheme::App {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied to 'app:'"));
+        }
+        if_native_platform! {{
+            write_atomic_native(PathBuf::from(self.path_omega()), data.as_ref(), options)
+        }}
+        if_browser! {{
+            let _ = (data, options);
+            unsupported_browser_sync_operation!();
+        }}
+    }
+
+    /// Replaces the contents of a file asynchronously, such that the file
+    /// contains either its previous contents or `data` even if the
+    /// application crashes while writing.
+    ///
+    /// # Browser support
+    ///
+    /// In the browser, the file is replaced when committing a writable
+    /// stream of the origin private file system, and `options.sync` is ignored.
+    ///
+    pub async fn write_atomic_async<T: AsRef<[u8]>>(&self, data: T, options: AtomicWriteOptions) -> io::Result<()> {
+        if self.scheme == FileScheme::App {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied to 'app:'"));
+        }
+        if_native_platform! {{
+            let (path, data) = (PathBuf::from(self.path_omega()), data.as_ref().to_vec());
+            tokio::task::spawn_blocking(move || write_atomic_native(path, &data, options)).await?
+        }}
+        if_browser! {{
+            let backup_path = options.keep_backup.then(|| self.backup_file().path_omega());
+            platforms::browser::write_atomic_async(self.path_omega(), data.as_ref(), backup_path).await
+        }}
+    }
+}
+
+if_native_platform! {
+    static TEMPORARY_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    /// Maximum number of symbolic links followed when resolving the written file.
+    const MAX_SYMBOLIC_LINKS: usize = 40;
+
+    /// Follows `path` while it is a symbolic link, including a link to
+    /// a file that does not exist yet.
+    fn resolve_symbolic_links(mut path: PathBuf) -> io::Result<PathBuf> {
+        for _ in 0..MAX_SYMBOLIC_LINKS {
+            match std::fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    let target = std::fs::read_link(&path)?;
+                    path = path.parent().map(|directory| directory.join(&target)).unwrap_or(target);
+                },
+                Ok(_) => return Ok(path),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(path),
+                Err(error) => return Err(error),
+            }
+        }
+        Err(io::Error::other("Too many levels of symbolic links"))
+    }
+
+    fn write_atomic_native(path: PathBuf, data: &[u8], options: AtomicWriteOptions) -> io::Result<()> {
+        let (Some(link_directory), Some(link_name)) = (path.parent(), path.file_name()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"));
+        };
+        let backup = link_directory.join(format!("{}.bak", link_name.to_string_lossy()));
+        let path = resolve_symbolic_links(path)?;
+        let (Some(directory), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"));
+        };
+        let name = name.to_string_lossy();
+        let temporary = directory.join(format!(".{name}.{}-{}.tmp", std::process::id(), TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        let permissions = match std::fs::metadata(&path) {
+            Ok(metadata) => Some(metadata.permissions()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+
+        let result = (|| {
+            let mut file = std::fs::File::create(&temporary)?;
+            file.write_all(data)?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            if options.sync {
+                file.sync_all()?;
+            }
+            drop(file);
+            if options.keep_backup && path.is_file() {
+                std::fs::copy(&path, &backup)?;
+            }
+            std::fs::rename(&temporary, &path)
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
+        result?;
+
+        // Persist the rename itself.
+        #[cfg(unix)]
+        if options.sync {
+            std::fs::File::open(directory)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use crate::{common::*, file::*};
+
+    #[test]
+    fn test_write_atomic() {
+        let directory = std::env::temp_dir().join(format!("agera-write-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = File::new(&directory.join("settings.json").to_string_lossy());
+
+        file.write_atomic("{}", default()).unwrap();
+        assert_eq!(file.read_utf8().unwrap(), "{}");
+        assert!(!file.backup_file().exists());
+
+        file.write_atomic(r#"{"volume":1}"#, AtomicWriteOptions { keep_backup: true, sync: false }).unwrap();
+        assert_eq!(file.read_utf8().unwrap(), r#"{"volume":1}"#);
+        assert_eq!(file.backup_file().read_utf8().unwrap(), "{}");
+        assert_eq!(file.parent().directory_listing().unwrap().len(), 2);
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(file.write_atomic_async("[]", default())).unwrap();
+        assert_eq!(file.read_utf8().unwrap(), "[]");
+        assert_eq!(File::new("app://settings.json").write_atomic("{}", default()).unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_preserves_file() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+        let directory = std::env::temp_dir().join(format!("agera-write-atomic-preserve-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let target = directory.join("secrets.json");
+        std::fs::write(&target, "{}").unwrap();
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o600)).unwrap();
+        symlink("secrets.json", directory.join("link.json")).unwrap();
+
+        let link = File::new(&directory.join("link.json").to_string_lossy());
+        link.write_atomic("[]", AtomicWriteOptions { keep_backup: true, sync: false }).unwrap();
+        assert!(std::fs::symlink_metadata(directory.join("link.json")).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "[]");
+        assert_eq!(std::fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(link.backup_file().read_utf8().unwrap(), "{}");
+
+        // A link to a missing file creates the file.
+        symlink("created.json", directory.join("dangling.json")).unwrap();
+        File::new(&directory.join("dangling.json").to_string_lossy()).write_atomic("1", default()).unwrap();
+        assert_eq!(std::fs::read_to_string(directory.join("created.json")).unwrap(), "1");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -116,6 +116,38 @@ export async function writeAsync(path, data) {
     }
 }
 
+/**
+ * Replaces the contents of a file by committing a writable stream,
+ * which is written to a swap file until it is closed. If `backupPath`
+ * is given, the previous contents are first copied to that file.
+ */
+export async function writeAtomicAsync(path, data, backupPath) {
+    let previous = null;
+    if (backupPath !== undefined && backupPath !== null) {
+        try {
+            previous = await getFileHandleAsync(path);
+        } catch (error) {
+            if (error !== errorConstants.NotFoundError) {
+                throw error;
+            }
+        }
+    }
+    const handle = await getFileHandleAsync(path, true);
+    try {
+        if (previous !== null) {
+            const backupHandle = await getFileHandleAsync(backupPath, true);
+            const backupWritable = await backupHandle.createWritable();
+            await backupWritable.write(await (await previous.getFile()).arrayBuffer());
+            await backupWritable.close();
+        }
+        const writable = await handle.createWritable();
+        await writable.write(data);
+        await writable.close();
+    } catch (error) {
+        throw transformError(error);
+    }
+}
+
 export async function modificationEpochMillisecondsAsync(path) {
     try {
         await getDirectoryHandleAsync(path);
@@ -144,6 +176,135 @@ export async function sizeAsync(path) {
     }
 }
 
+/**
+ * A file opened for streaming. Inside a dedicated worker, the file is
+ * accessed through a synchronous access handle; elsewhere, writes go
+ * to a writable that is kept open until a read observes them or
+ * the stream is flushed.
+ */
+export class FileStream {
+    constructor(handle, access) {
+        this.handle = handle;
+        this.access = access;
+        this.writable = null;
+        this.dirty = false;
+    }
+
+    static async openAsync(path, create, truncate) {
+        const handle = await getFileHandleAsync(path, create);
+        let access = null;
+        if (typeof WorkerGlobalScope !== "undefined" && typeof handle.createSyncAccessHandle === "function") {
+            try {
+                access = await handle.createSyncAccessHandle();
+            } catch (error) {
+                throw transformError(error);
+            }
+        }
+        const stream = new FileStream(handle, access);
+        if (truncate) {
+            await stream.setSizeAsync(0);
+            await stream.flushAsync();
+        }
+        return stream;
+    }
+
+    async readAsync(position, length) {
+        try {
+            if (this.access !== null) {
+                const buffer = new Uint8Array(length);
+                const count = this.access.read(buffer, { at: position });
+                return buffer.subarray(0, count);
+            }
+            if (this.dirty) {
+                await this.flushAsync();
+            }
+            const file = await this.handle.getFile();
+            return new Uint8Array(await file.slice(position, position + length).arrayBuffer());
+        } catch (error) {
+            throw transformError(error);
+        }
+    }
+
+    async writeAsync(position, data) {
+        try {
+            if (this.access !== null) {
+                this.access.write(data, { at: position });
+                return;
+            }
+            await (await this.writableAsync()).write({ type: "write", position, data });
+            this.dirty = true;
+        } catch (error) {
+            throw transformError(error);
+        }
+    }
+
+    async sizeAsync() {
+        try {
+            if (this.access !== null) {
+                return this.access.getSize();
+            }
+            if (this.dirty) {
+                await this.flushAsync();
+            }
+            return (await this.handle.getFile()).size;
+        } catch (error) {
+            throw transformError(error);
+        }
+    }
+
+    async setSizeAsync(size) {
+        try {
+            if (this.access !== null) {
+                this.access.truncate(size);
+                return;
+            }
+            await (await this.writableAsync()).truncate(size);
+            this.dirty = true;
+        } catch (error) {
+            throw transformError(error);
+        }
+    }
+
+    async flushAsync() {
+        if (this.access !== null) {
+            try {
+                this.access.flush();
+            } catch (error) {
+                throw transformError(error);
+            }
+            return;
+        }
+        if (this.writable === null) {
+            return;
+        }
+        const writable = this.writable;
+        this.writable = null;
+        this.dirty = false;
+        try {
+            await writable.close();
+        } catch (error) {
+            throw transformError(error);
+        }
+    }
+
+    async closeAsync() {
+        if (this.access !== null) {
+            const access = this.access;
+            this.access = null;
+            access.close();
+            return;
+        }
+        await this.flushAsync();
+    }
+
+    async writableAsync() {
+        if (this.writable === null) {
+            this.writable = await this.handle.createWritable({ keepExistingData: true });
+        }
+        return this.writable;
+    }
+}
+
 /**
  * @throws {number} An error constant.
  */
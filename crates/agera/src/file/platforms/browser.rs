@@ -1,6 +1,7 @@
-use crate::common::*;
-use std::io;
-use wasm_bindgen::prelude::*;
+use crate::{common::*, file::FileMode};
+use futures::{future::LocalBoxFuture, io::{AsyncRead, AsyncSeek, AsyncWrite}, ready, Future};
+use std::{io::{self, SeekFrom}, pin::Pin, task::{Context, Poll}};
+use wasm_bindgen::{prelude::*, JsCast};
 
 #[wasm_bindgen(module = "browser.js")]
 extern "C" {
@@ -42,6 +43,32 @@ extern "C" {
 
     #[wasm_bindgen(catch, js_name = sizeAsync)]
     async fn js_size_async(path: String) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, js_name = writeAtomicAsync)]
+    async fn js_write_atomic_async(path: String, data: JsValue, backup_path: Option<String>) -> Result<JsValue, JsValue>;
+
+    type JSFileStream;
+
+    #[wasm_bindgen(catch, static_method_of = JSFileStream, js_class = "FileStream", js_name = openAsync)]
+    async fn open_async(path: String, create: bool, truncate: bool) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "FileStream", js_name = readAsync)]
+    async fn read_async(this: &JSFileStream, position: f64, length: f64) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "FileStream", js_name = writeAsync)]
+    async fn write_async(this: &JSFileStream, position: f64, data: JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "FileStream", js_name = sizeAsync)]
+    async fn size_async(this: &JSFileStream) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "FileStream", js_name = setSizeAsync)]
+    async fn set_size_async(this: &JSFileStream, size: f64) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "FileStream", js_name = flushAsync)]
+    async fn flush_async(this: &JSFileStream) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(catch, method, js_class = "FileStream", js_name = closeAsync)]
+    async fn close_async(this: &JSFileStream) -> Result<JsValue, JsValue>;
 }
 
 pub async fn exists_async(path: String) -> bool {
@@ -98,6 +125,11 @@ pub async fn write_async(path: String, data: &[u8]) -> io::Result<()> {
     js_write_async(path, uint8array.buffer().into()).await.map(|_| ()).map_err(|error| js_io_error_to_rs_io_error(error, false))
 }
 
+pub async fn write_atomic_async(path: String, data: &[u8], backup_path: Option<String>) -> io::Result<()> {
+    let uint8array = js_sys::Uint8Array::from(data);
+    js_write_atomic_async(path, uint8array.into(), backup_path).await.map(|_| ()).map_err(|error| js_io_error_to_rs_io_error(error, false))
+}
+
 pub async fn modification_date_async(path: String) -> io::Result<Option<std::time::SystemTime>> {
     let ms = js_modification_epoch_milliseconds_async(path).await.map_err(|error| js_io_error_to_rs_io_error(error, false))?;
     if ms.is_undefined() {
@@ -112,6 +144,163 @@ pub async fn size_async(path: String) -> io::Result<usize> {
     Ok(unsafe { size.as_f64().unwrap().to_int_unchecked() })
 }
 
+/// A file of the origin private file system opened for streaming,
+/// whose position and length are tracked on the Rust side.
+///
+/// The stream implements the `AsyncRead`, `AsyncWrite` and `AsyncSeek`
+/// traits of the `futures::io` module. The file is closed when the stream
+/// is dropped.
+pub struct FileStream {
+    stream: JSFileStream,
+    append: bool,
+    position: u64,
+    length: u64,
+    pending: PendingOperation,
+}
+
+/// An operation started by a `poll_*` method that has not completed yet.
+enum PendingOperation {
+    None,
+    Read(LocalBoxFuture<'static, Result<JsValue, JsValue>>),
+    Write(LocalBoxFuture<'static, Result<JsValue, JsValue>>, u64, usize),
+    Flush(LocalBoxFuture<'static, Result<JsValue, JsValue>>),
+}
+
+impl FileStream {
+    pub async fn open_async(path: String, mode: FileMode) -> io::Result<Self> {
+        let stream: JSFileStream = JSFileStream::open_async(path, mode.is_write() && mode.is_create(), mode.is_write() && mode.is_truncate() && !mode.is_append()).await
+            .map_err(|error| js_io_error_to_rs_io_error(error, false))?
+            .unchecked_into();
+        let length = stream.size_async().await.map_err(|error| js_io_error_to_rs_io_error(error, false))?;
+        Ok(Self { stream, append: mode.is_append(), position: 0, length: length.as_f64().unwrap() as u64, pending: PendingOperation::None })
+    }
+
+    pub async fn read_async(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let data = self.stream.read_async(self.position as f64, buffer.len() as f64).await
+            .map_err(|error| js_io_error_to_rs_io_error(error, false))?;
+        Ok(self.complete_read(data, buffer))
+    }
+
+    pub async fn write_all_async(&mut self, data: &[u8]) -> io::Result<()> {
+        let position = self.write_position();
+        let uint8array = js_sys::Uint8Array::from(data);
+        self.stream.write_async(position as f64, uint8array.into()).await
+            .map_err(|error| js_io_error_to_rs_io_error(error, false))?;
+        self.complete_write(position, data.len());
+        Ok(())
+    }
+
+    pub async fn seek_async(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.seek(position)
+    }
+
+    pub async fn len_async(&mut self) -> io::Result<u64> {
+        Ok(self.length)
+    }
+
+    pub async fn set_len_async(&mut self, length: u64) -> io::Result<()> {
+        self.stream.set_size_async(length as f64).await.map_err(|error| js_io_error_to_rs_io_error(error, false))?;
+        self.length = length;
+        Ok(())
+    }
+
+    pub async fn flush_async(&mut self) -> io::Result<()> {
+        self.stream.flush_async().await.map(|_| ()).map_err(|error| js_io_error_to_rs_io_error(error, false))
+    }
+
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Seek to a negative position"))?;
+        Ok(self.position)
+    }
+
+    /// Returns the position at which the next write starts,
+    /// which is the end of the file in append mode.
+    fn write_position(&self) -> u64 {
+        if self.append { self.length } else { self.position }
+    }
+
+    fn complete_read(&mut self, data: JsValue, buffer: &mut [u8]) -> usize {
+        let data = js_sys::Uint8Array::new(&data);
+        let length = (data.length() as usize).min(buffer.len());
+        data.subarray(0, length as u32).copy_to(&mut buffer[..length]);
+        self.position += length as u64;
+        length
+    }
+
+    fn complete_write(&mut self, position: u64, length: usize) {
+        self.position = position + length as u64;
+        self.length = self.length.max(self.position);
+    }
+}
+
+impl AsyncRead for FileStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buffer: &mut [u8]) -> Poll<io::Result<usize>> {
+        if !matches!(self.pending, PendingOperation::Read(_)) {
+            let stream = self.stream.clone();
+            let (position, length) = (self.position as f64, buffer.len() as f64);
+            self.pending = PendingOperation::Read(Box::pin(async move { stream.read_async(position, length).await }));
+        }
+        let PendingOperation::Read(future) = &mut self.pending else { unreachable!() };
+        let result = ready!(future.as_mut().poll(cx));
+        self.pending = PendingOperation::None;
+        let data = result.map_err(|error| js_io_error_to_rs_io_error(error, false))?;
+        Poll::Ready(Ok(self.complete_read(data, buffer)))
+    }
+}
+
+impl AsyncWrite for FileStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        if !matches!(self.pending, PendingOperation::Write(..)) {
+            let stream = self.stream.clone();
+            let position = self.write_position();
+            let uint8array = js_sys::Uint8Array::from(data);
+            self.pending = PendingOperation::Write(Box::pin(async move { stream.write_async(position as f64, uint8array.into()).await }), position, data.len());
+        }
+        let PendingOperation::Write(future, position, length) = &mut self.pending else { unreachable!() };
+        let (position, length) = (*position, *length);
+        let result = ready!(future.as_mut().poll(cx));
+        self.pending = PendingOperation::None;
+        result.map_err(|error| js_io_error_to_rs_io_error(error, false))?;
+        self.complete_write(position, length);
+        Poll::Ready(Ok(length))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if !matches!(self.pending, PendingOperation::Flush(_)) {
+            let stream = self.stream.clone();
+            self.pending = PendingOperation::Flush(Box::pin(async move { stream.flush_async().await }));
+        }
+        let PendingOperation::Flush(future) = &mut self.pending else { unreachable!() };
+        let result = ready!(future.as_mut().poll(cx));
+        self.pending = PendingOperation::None;
+        Poll::Ready(result.map(|_| ()).map_err(|error| js_io_error_to_rs_io_error(error, false)))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl AsyncSeek for FileStream {
+    fn poll_seek(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, position: SeekFrom) -> Poll<io::Result<u64>> {
+        Poll::Ready(self.seek(position))
+    }
+}
+
+impl Drop for FileStream {
+    fn drop(&mut self) {
+        let stream = self.stream.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let _ = stream.close_async().await;
+        });
+    }
+}
+
 pub(crate) fn js_io_error_to_rs_io_error(error: JsValue, is_directory: bool) -> io::Error {
     let error = error.as_f64().unwrap();
     if error == 0.0 {
@@ -0,0 +1,368 @@
+use std::io::{self, SeekFrom};
+use crate::platforms::{if_native_platform, if_browser};
+use super::*;
+
+if_native_platform! {
+    use std::{pin::Pin, task::{Context, Poll}};
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+}
+
+if_browser! {
+    use std::{pin::Pin, task::{Context, Poll}};
+    use futures::io::{AsyncRead, AsyncSeek, AsyncWrite};
+}
+
+/// The mode in which `File::open()` and `File::open_async()` open a file.
+///
+/// ```ignore
+/// let mut log = File::new("app-storage://app.log").open_async(FileMode::APPEND).await?;
+/// log.write_all_async(b"Started\n").await?;
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FileMode {
+    read: bool,
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+}
+
+impl FileMode {
+    /// Opens an existing file for reading.
+    pub const READ: FileMode = FileMode { read: true, write: false, append: false, truncate: false, create: false };
+    /// Opens a file for writing, creating it if it does not exist
+    /// and truncating it otherwise.
+    pub const WRITE: FileMode = FileMode { read: false, write: true, append: false, truncate: true, create: true };
+    /// Opens a file for writing at its end, creating it if it does not exist.
+    pub const APPEND: FileMode = FileMode { read: false, write: true, append: true, truncate: false, create: true };
+    /// Opens a file for reading and writing, creating it if it does not exist.
+    pub const READ_WRITE: FileMode = FileMode { read: true, write: true, append: false, truncate: false, create: true };
+
+    /// Returns this mode with reading enabled or disabled.
+    pub const fn with_read(mut self, value: bool) -> Self {
+        self.read = value;
+        self
+    }
+
+    /// Returns this mode with truncation enabled or disabled.
+    /// Truncation only applies to writable modes.
+    pub const fn with_truncate(mut self, value: bool) -> Self {
+        self.truncate = value;
+        self
+    }
+
+    /// Returns this mode with creation of a missing file enabled or disabled.
+    pub const fn with_create(mut self, value: bool) -> Self {
+        self.create = value;
+        self
+    }
+
+    pub const fn is_read(&self) -> bool {
+        self.read
+    }
+
+    /// Indicates whether the mode writes, including appending.
+    pub const fn is_write(&self) -> bool {
+        self.write
+    }
+
+    pub const fn is_append(&self) -> bool {
+        self.append
+    }
+
+    pub const fn is_truncate(&self) -> bool {
+        self.truncate
+    }
+
+    pub const fn is_create(&self) -> bool {
+        self.create
+    }
+
+    if_native_platform! {
+        fn std_options(&self) -> std::fs::OpenOptions {
+            let mut options = std::fs::OpenOptions::new();
+            options.read(self.read)
+                .write(self.write && !self.append)
+                .append(self.append)
+                .truncate(self.write && self.truncate && !self.append)
+                .create(self.write && self.create);
+            options
+        }
+    }
+}
+
+impl File {
+    /// Opens a file for streaming, synchronously.
+    ///
+    /// # Browser support
+    ///
+    /// This is a synchronous operation, therefore it is not supported
+    /// in the browser.
+    ///
+    pub fn open(&self, mode: FileMode) -> io::Result<FileStream> {
+        self.check_open_mode(mode)?;
+        if_native_platform! {{
+            let file = mode.std_options().open(self.path_omega())?;
+            Ok(FileStream { inner: tokio::fs::File::from_std(file), mode })
+        }}
+        if_browser! {{
+            unsupported_browser_sync_operation!();
+        }}
+    }
+
+    /// Opens a file for streaming, asynchronously.
+    pub async fn open_async(&self, mode: FileMode) -> io::Result<FileStream> {
+        self.check_open_mode(mode)?;
+        if_native_platform! {{
+            let file = tokio::fs::OpenOptions::from(mode.std_options()).open(&self.path_omega()).await?;
+            Ok(FileStream { inner: file, mode })
+        }}
+        if_browser! {{
+            let inner = platforms::browser::FileStream::open_async(self.path_omega(), mode).await?;
+            Ok(FileStream { inner, mode })
+        }}
+    }
+
+    fn check_open_mode(&self, mode: FileMode) -> io::Result<()> {
+        if mode.write && self.scheme == FileScheme::App {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied to 'app:'"));
+        }
+        if !(mode.read || mode.write) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "File mode neither reads nor writes"));
+        }
+        Ok(())
+    }
+}
+
+/// A file opened for streaming, returned by `File::open()` and `File::open_async()`.
+///
+/// The stream has a position, from which reads and writes happen and which
+/// they advance. In `FileMode::APPEND`, writes always happen at the end of the file.
+///
+/// ```ignore
+/// let mut video = File::new("app-storage://recording.webm").open_async(FileMode::READ).await?;
+/// loop {
+///     let chunk = video.read_chunk_async(64 * 1024).await?;
+///     if chunk.is_empty() {
+///         break;
+///     }
+///     upload(chunk).await;
+/// }
+/// ```
+///
+/// # Platform support
+///
+/// On native platforms, `FileStream` implements the `AsyncRead`, `AsyncWrite` and
+/// `AsyncSeek` traits of `agera::platforms::tokio`. In the browser, it
+/// implements the same traits of the `futures::io` module instead; it is
+/// backed by the origin private file system and written data becomes
+/// visible to other readers of the file after `flush_async()`.
+pub struct FileStream {
+    inner: StreamInner,
+    mode: FileMode,
+}
+
+if_native_platform! {
+    type StreamInner = tokio::fs::File;
+}
+
+if_browser! {
+    type StreamInner = platforms::browser::FileStream;
+}
+
+impl FileStream {
+    pub fn mode(&self) -> FileMode {
+        self.mode
+    }
+
+    /// Reads into `buffer` from the current position, returning the
+    /// number of bytes read, which is zero at the end of the file.
+    pub async fn read_async(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if_native_platform! {{
+            self.inner.read(buffer).await
+        }}
+        if_browser! {{
+            self.inner.read_async(buffer).await
+        }}
+    }
+
+    /// Reads up to `max_length` bytes from the current position,
+    /// returning empty bytes at the end of the file.
+    pub async fn read_chunk_async(&mut self, max_length: usize) -> io::Result<Bytes> {
+        let mut buffer = vec![0; max_length];
+        let length = self.read_async(&mut buffer).await?;
+        buffer.truncate(length);
+        Ok(Bytes::from(buffer))
+    }
+
+    /// Writes all of `data` at the current position, or at the end
+    /// of the file in append mode.
+    pub async fn write_all_async(&mut self, data: &[u8]) -> io::Result<()> {
+        if_native_platform! {{
+            self.inner.write_all(data).await
+        }}
+        if_browser! {{
+            self.inner.write_all_async(data).await
+        }}
+    }
+
+    /// Moves the current position, returning the new position
+    /// from the start of the file.
+    pub async fn seek_async(&mut self, position: SeekFrom) -> io::Result<u64> {
+        if_native_platform! {{
+            self.inner.seek(position).await
+        }}
+        if_browser! {{
+            self.inner.seek_async(position).await
+        }}
+    }
+
+    /// The current position from the start of the file.
+    pub async fn position_async(&mut self) -> io::Result<u64> {
+        self.seek_async(SeekFrom::Current(0)).await
+    }
+
+    /// Flushes written data.
+    pub async fn flush_async(&mut self) -> io::Result<()> {
+        if_native_platform! {{
+            self.inner.flush().await
+        }}
+        if_browser! {{
+            self.inner.flush_async().await
+        }}
+    }
+
+    /// Flushes written data and, on native platforms, waits
+    /// for it to reach the storage device.
+    pub async fn sync_all_async(&mut self) -> io::Result<()> {
+        if_native_platform! {{
+            self.inner.flush().await?;
+            self.inner.sync_all().await
+        }}
+        if_browser! {{
+            self.inner.flush_async().await
+        }}
+    }
+
+    /// The size of the file, in bytes.
+    pub async fn len_async(&mut self) -> io::Result<u64> {
+        if_native_platform! {{
+            self.inner.metadata().await.map(|metadata| metadata.len())
+        }}
+        if_browser! {{
+            self.inner.len_async().await
+        }}
+    }
+
+    /// Truncates or extends the file to `length` bytes, without
+    /// moving the current position.
+    pub async fn set_len_async(&mut self, length: u64) -> io::Result<()> {
+        if_native_platform! {{
+            self.inner.set_len(length).await
+        }}
+        if_browser! {{
+            self.inner.set_len_async(length).await
+        }}
+    }
+}
+
+if_native_platform! {
+    impl AsyncRead for FileStream {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buffer: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buffer)
+        }
+    }
+
+    impl AsyncWrite for FileStream {
+        fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write(cx, data)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_shutdown(cx)
+        }
+    }
+
+    impl AsyncSeek for FileStream {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+            Pin::new(&mut self.inner).start_seek(position)
+        }
+
+        fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_complete(cx)
+        }
+    }
+}
+
+if_browser! {
+    impl AsyncRead for FileStream {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buffer: &mut [u8]) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_read(cx, buffer)
+        }
+    }
+
+    impl AsyncWrite for FileStream {
+        fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.inner).poll_write(cx, data)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.inner).poll_close(cx)
+        }
+    }
+
+    impl AsyncSeek for FileStream {
+        fn poll_seek(mut self: Pin<&mut Self>, cx: &mut Context<'_>, position: SeekFrom) -> Poll<io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_seek(cx, position)
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::io::SeekFrom;
+    use crate::file::*;
+
+    #[test]
+    fn test_file_stream() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let directory = std::env::temp_dir().join(format!("agera-file-stream-{}", std::process::id()));
+            std::fs::create_dir_all(&directory).unwrap();
+            let file = File::new(&directory.join("stream.bin").to_string_lossy());
+
+            let mut stream = file.open_async(FileMode::WRITE).await.unwrap();
+            stream.write_all_async(b"hello world").await.unwrap();
+            stream.flush_async().await.unwrap();
+            drop(stream);
+
+            let mut stream = file.open_async(FileMode::APPEND).await.unwrap();
+            stream.write_all_async(b"!").await.unwrap();
+            stream.flush_async().await.unwrap();
+            drop(stream);
+
+            let mut stream = file.open(FileMode::READ_WRITE).unwrap();
+            assert_eq!(stream.len_async().await.unwrap(), 12);
+            assert_eq!(&stream.read_chunk_async(5).await.unwrap()[..], b"hello");
+            assert_eq!(stream.seek_async(SeekFrom::End(-6)).await.unwrap(), 6);
+            stream.write_all_async(b"agera").await.unwrap();
+            stream.set_len_async(11).await.unwrap();
+            stream.seek_async(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(&stream.read_chunk_async(64).await.unwrap()[..], b"hello agera");
+            assert!(stream.read_chunk_async(64).await.unwrap().is_empty());
+            drop(stream);
+
+            assert_eq!(file.open(FileMode::READ.with_create(true)).unwrap().mode(), FileMode::READ.with_create(true));
+            assert_eq!(File::new("app://data.bin").open_async(FileMode::WRITE).await.err().unwrap().kind(), std::io::ErrorKind::PermissionDenied);
+            std::fs::remove_dir_all(&directory).unwrap();
+        });
+    }
+}
@@ -0,0 +1,317 @@
+use std::{cmp::Ordering, io, sync::Arc};
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use glob::{MatchOptions, Pattern};
+use super::*;
+
+pub use glob::PatternError;
+
+/// Options used to match glob patterns, consistent with the
+/// `installFiles` patterns of the application descriptor:
+/// wildcards do not match path separators, except for `**`.
+const GLOB_MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+type WalkComparator = Arc<dyn Fn(&File, &File) -> Ordering + Send + Sync>;
+
+/// A recursive walk over the entries of a directory, returned by `File::walk()`.
+///
+/// Entries are visited depth first, each directory before its entries.
+/// The walked directory itself is not yielded. Glob patterns are matched against
+/// the path of an entry relative to the walked directory, using `/` as separator.
+/// An entry is yielded if it matches any include pattern, or if there are no
+/// include patterns, and matches no exclude pattern. Directories are
+/// walked regardless of whether they are yielded, except for directories
+/// excluded along with their entries, such as by a `dir` or `dir/**`
+/// exclude pattern, which are not walked at all.
+///
+/// ```ignore
+/// let walk = File::new("app-storage://documents").walk()
+///     .max_depth(4)
+///     .sort_by_name()
+///     .include("**/*.md")?
+///     .exclude(".trash/**")?;
+/// for file in walk.iter() {
+///     let file = file?;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Walk {
+    root: File,
+    max_depth: Option<usize>,
+    follow_symbolic_links: bool,
+    sort: Option<WalkComparator>,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    /// Patterns of the directories whose entries are all excluded,
+    /// which are the exclude patterns and their `/**` prefixes.
+    prune: Vec<Pattern>,
+}
+
+impl File {
+    /// Walks the entries of a directory recursively. See [`Walk`].
+    pub fn walk(&self) -> Walk {
+        Walk {
+            root: self.clone(),
+            max_depth: None,
+            follow_symbolic_links: false,
+            sort: None,
+            include: vec![],
+            exclude: vec![],
+            prune: vec![],
+        }
+    }
+}
+
+impl Walk {
+    /// Limits the depth of yielded entries, where the entries
+    /// of the walked directory have a depth of 1.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Whether to walk directories found through symbolic links.
+    /// Defaults to `false`, in which case symbolic links are
+    /// yielded but not walked. Directories already walked
+    /// are not walked again when following links.
+    pub fn follow_symbolic_links(mut self, value: bool) -> Self {
+        self.follow_symbolic_links = value;
+        self
+    }
+
+    /// Visits the entries of each directory in name order.
+    /// By default, entries are visited in the order the
+    /// file system lists them.
+    pub fn sort_by_name(self) -> Self {
+        self.sort_by(|a, b| a.name().cmp(&b.name()))
+    }
+
+    /// Visits the entries of each directory in the given order.
+    pub fn sort_by(mut self, compare: impl Fn(&File, &File) -> Ordering + Send + Sync + 'static) -> Self {
+        self.sort = Some(Arc::new(compare));
+        self
+    }
+
+    /// Adds an include glob pattern.
+    pub fn include(mut self, pattern: &str) -> Result<Self, PatternError> {
+        self.include.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Adds an exclude glob pattern. Directories matching the pattern,
+    /// or matching it once followed by `/**`, are not walked.
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, PatternError> {
+        let compiled = Pattern::new(pattern)?;
+        self.exclude.push(compiled.clone());
+        self.prune.push(compiled);
+        if let Some(directory) = pattern.strip_suffix("/**") {
+            self.prune.push(Pattern::new(directory)?);
+        }
+        Ok(self)
+    }
+
+    /// Walks synchronously.
+    ///
+    /// # Browser support
+    ///
+    /// This is a synchronous operation, therefore it is not supported
+    /// in the browser.
+    ///
+    pub fn iter(&self) -> WalkIter {
+        WalkIter { state: WalkState::new(self.clone()) }
+    }
+
+    /// Walks asynchronously, listing each directory as the stream reaches it.
+    pub fn stream(&self) -> LocalBoxStream<'static, io::Result<File>> {
+        stream::unfold(WalkState::new(self.clone()), |mut state| async move {
+            state.next_async().await.map(|entry| (entry, state))
+        }).boxed_local()
+    }
+
+    fn matches(&self, relative: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches_with(relative, GLOB_MATCH_OPTIONS)))
+            && !self.exclude.iter().any(|pattern| pattern.matches_with(relative, GLOB_MATCH_OPTIONS))
+    }
+
+    /// Indicates whether the directory at `relative` is excluded along with its entries.
+    fn prunes(&self, relative: &str) -> bool {
+        self.prune.iter().any(|pattern| pattern.matches_with(relative, GLOB_MATCH_OPTIONS))
+    }
+}
+
+/// Iterator returned by `Walk::iter()`.
+pub struct WalkIter {
+    state: WalkState,
+}
+
+impl Iterator for WalkIter {
+    type Item = io::Result<File>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.state.next()
+    }
+}
+
+struct WalkEntry {
+    file: File,
+    relative: String,
+    depth: usize,
+}
+
+struct WalkState {
+    walk: Walk,
+    started: bool,
+    /// Entries left to visit, with the next entry last.
+    pending: Vec<io::Result<WalkEntry>>,
+    /// URLs of the walked directories, tracked when following symbolic links.
+    walked: HashSet<String>,
+}
+
+impl WalkState {
+    fn new(walk: Walk) -> Self {
+        Self { walk, started: false, pending: vec![], walked: HashSet::new() }
+    }
+
+    fn push_listing(&mut self, relative: &str, depth: usize, listing: io::Result<Vec<File>>) {
+        let mut listing = match listing {
+            Ok(listing) => listing,
+            Err(error) => {
+                self.pending.push(Err(error));
+                return;
+            },
+        };
+        if let Some(compare) = &self.walk.sort {
+            listing.sort_by(|a, b| compare(a, b));
+        }
+        for file in listing.into_iter().rev() {
+            let name = file.name();
+            let relative = if relative.is_empty() { name } else { format!("{relative}/{name}") };
+            self.pending.push(Ok(WalkEntry { file, relative, depth: depth + 1 }));
+        }
+    }
+
+    /// Indicates whether the entries of a directory at `depth` are within the depth limit.
+    fn within_depth(&self, depth: usize) -> bool {
+        self.walk.max_depth.is_none_or(|max_depth| depth < max_depth)
+    }
+
+    fn next(&mut self) -> Option<io::Result<File>> {
+        if !self.started {
+            self.started = true;
+            if self.walk.follow_symbolic_links {
+                self.walked.insert(self.walk.root.canonicalize().url());
+            }
+            if self.within_depth(0) {
+                let listing = self.walk.root.directory_listing();
+                self.push_listing("", 0, listing);
+            }
+        }
+        while let Some(entry) = self.pending.pop() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+            if self.within_depth(entry.depth) && !self.walk.prunes(&entry.relative) && entry.file.is_directory() {
+                let walk = if self.walk.follow_symbolic_links {
+                    self.walked.insert(entry.file.canonicalize().url())
+                } else {
+                    !entry.file.is_symbolic_link()
+                };
+                if walk {
+                    let listing = entry.file.directory_listing();
+                    self.push_listing(&entry.relative, entry.depth, listing);
+                }
+            }
+            if self.walk.matches(&entry.relative) {
+                return Some(Ok(entry.file));
+            }
+        }
+        None
+    }
+
+    async fn next_async(&mut self) -> Option<io::Result<File>> {
+        if !self.started {
+            self.started = true;
+            if self.walk.follow_symbolic_links {
+                self.walked.insert(self.walk.root.canonicalize_async().await.url());
+            }
+            if self.within_depth(0) {
+                let listing = self.walk.root.directory_listing_async().await;
+                self.push_listing("", 0, listing);
+            }
+        }
+        while let Some(entry) = self.pending.pop() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+            if self.within_depth(entry.depth) && !self.walk.prunes(&entry.relative) && entry.file.is_directory_async().await {
+                let walk = if self.walk.follow_symbolic_links {
+                    self.walked.insert(entry.file.canonicalize_async().await.url())
+                } else {
+                    !entry.file.is_symbolic_link_async().await
+                };
+                if walk {
+                    let listing = entry.file.directory_listing_async().await;
+                    self.push_listing(&entry.relative, entry.depth, listing);
+                }
+            }
+            if self.walk.matches(&entry.relative) {
+                return Some(Ok(entry.file));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use futures::StreamExt;
+    use crate::file::*;
+
+    #[test]
+    fn test_walk() {
+        let directory = std::env::temp_dir().join(format!("agera-walk-{}", std::process::id()));
+        for path in ["docs/a.md", "docs/b.txt", "docs/deep/c.md", "notes.md", ".trash/d.md"] {
+            let path = directory.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&directory, directory.join("docs/loop")).unwrap();
+        let root = File::new(&directory.to_string_lossy());
+        let relative = |files: Vec<File>| files.iter().map(|file| root.relative(file)).collect::<Vec<_>>();
+
+        let walk = root.walk().sort_by_name();
+        assert_eq!(
+            relative(walk.iter().map(Result::unwrap).collect()),
+            [".trash", ".trash/d.md", "docs", "docs/a.md", "docs/b.txt", "docs/deep", "docs/deep/c.md", "docs/loop", "notes.md"],
+        );
+        assert_eq!(relative(walk.clone().max_depth(1).iter().map(Result::unwrap).collect()), [".trash", "docs", "notes.md"]);
+
+        let markdown = walk.clone().include("**/*.md").unwrap().exclude(".trash/**").unwrap();
+        assert_eq!(relative(markdown.iter().map(Result::unwrap).collect()), ["docs/a.md", "docs/deep/c.md", "notes.md"]);
+        assert_eq!(relative(walk.clone().include("*.md").unwrap().iter().map(Result::unwrap).collect()), ["notes.md"]);
+
+        // Excluded directories are not walked.
+        let pruned = walk.clone().exclude("docs").unwrap().exclude(".trash/**").unwrap();
+        assert_eq!(relative(pruned.iter().map(Result::unwrap).collect()), [".trash", "notes.md"]);
+
+        // Following the link does not walk the directory again.
+        #[cfg(unix)]
+        assert_eq!(walk.clone().follow_symbolic_links(true).iter().count(), 9);
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let streamed: Vec<File> = runtime.block_on(markdown.stream().map(Result::unwrap).collect());
+        assert_eq!(relative(streamed), ["docs/a.md", "docs/deep/c.md", "notes.md"]);
+        let streamed: Vec<File> = runtime.block_on(pruned.stream().map(Result::unwrap).collect());
+        assert_eq!(relative(streamed), [".trash", "notes.md"]);
+
+        assert!(root.resolve_path("missing").walk().iter().next().unwrap().is_err());
+        assert!(root.walk().include("[").is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+use std::{pin::pin, sync::Arc};
+use crate::{platforms::{if_native_platform, if_browser}, timer::{self, CancellationToken, Duration, MissedTickBehavior, WaitForCancellation}, util::future};
+use super::*;
+
+/// The period at which a `FileWatcher` reads pending change notifications.
+pub const FILE_WATCH_NOTIFICATION_PERIOD: Duration = Duration::from_millis(100);
+
+/// The default period at which a polling `FileWatcher` scans for changes.
+pub const FILE_WATCH_POLL_PERIOD: Duration = Duration::from_secs(1);
+
+/// A change reported by a `FileWatcher`.
+#[derive(Clone, PartialEq, Eq)]
+pub enum FileWatchEvent {
+    Created(File),
+    Modified(File),
+    Deleted(File),
+    /// A file or directory was moved within the watched directory.
+    /// Polling watchers report moves as a deletion followed by a creation.
+    Renamed {
+        from: File,
+        to: File,
+    },
+    /// Change notifications were lost, such as when too many changes happened
+    /// at once. Any change to the watched file, given by this event, or to its
+    /// descendants may have been missed, so it should be scanned again.
+    Overflow(File),
+}
+
+impl FileWatchEvent {
+    /// The file affected by the change, which for renames is the new path.
+    pub fn file(&self) -> &File {
+        match self {
+            Self::Created(file) | Self::Modified(file) | Self::Deleted(file) | Self::Overflow(file) => file,
+            Self::Renamed { to, .. } => to,
+        }
+    }
+}
+
+impl File {
+    /// Watches a file or directory for changes, returning a `FileWatcher`
+    /// whose `on_event()` emitter reports them. If `recursive` is true,
+    /// changes within subdirectories of a directory are reported as well.
+    ///
+    /// On Linux and Android, changes to `file:` and `app-storage:` files are
+    /// reported through inotify. Elsewhere, and for files that cannot be watched
+    /// that way, such as missing files, the file is scanned for changes every
+    /// `FILE_WATCH_POLL_PERIOD`. When the watched file or directory is deleted
+    /// or replaced, such as by `File::write_atomic()` or by an editor saving
+    /// through a rename, inotify watchers report it as deleted, then as created
+    /// once it exists again, and keep watching it.
+    ///
+    /// ```ignore
+    /// let watcher = File::new("app-storage://documents").watch(true);
+    /// watcher.on_event().listener(|event| {
+    ///     if let FileWatchEvent::Modified(file) = event {
+    ///         reload(&file);
+    ///     }
+    /// });
+    /// ```
+    pub fn watch(&self, recursive: bool) -> FileWatcher {
+        if_native_platform! {{
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            if let Ok(inotify) = inotify::Inotify::new(self, recursive) {
+                return FileWatcher::start(WatchBackend::Inotify(inotify), FILE_WATCH_NOTIFICATION_PERIOD);
+            }
+        }}
+        self.watch_polling(recursive, FILE_WATCH_POLL_PERIOD)
+    }
+
+    /// Watches a file or directory for changes by scanning it every `period`.
+    /// See [`File::watch`].
+    pub fn watch_polling(&self, recursive: bool, period: Duration) -> FileWatcher {
+        FileWatcher::start(WatchBackend::Polling { root: self.clone(), recursive, snapshot: None }, period)
+    }
+}
+
+/// Watches a file or directory for changes, returned by `File::watch()`.
+/// Watching stops when the watcher is stopped or dropped.
+pub struct FileWatcher {
+    on_event: Arc<EventEmitter<FileWatchEvent>>,
+    token: CancellationToken,
+}
+
+impl FileWatcher {
+    fn start(mut backend: WatchBackend, period: Duration) -> Self {
+        let on_event = Arc::new(EventEmitter::new());
+        let token = CancellationToken::new();
+        let mut ticker = timer::ticker(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        future::exec({
+            let (on_event, token) = (Arc::clone(&on_event), token.clone());
+            async move {
+                loop {
+                    if let futures::future::Either::Right(_) = futures::future::select(pin!(ticker.tick()), token.cancelled()).await {
+                        break;
+                    }
+                    for event in backend.changes().await {
+                        if token.is_cancelled() {
+                            break;
+                        }
+                        on_event.emit(event);
+                    }
+                }
+            }
+        });
+        Self { on_event, token }
+    }
+
+    /// Emits the changes to the watched file or directory.
+    pub fn on_event(&self) -> &EventEmitter<FileWatchEvent> {
+        &self.on_event
+    }
+
+    pub fn stop(&self) {
+        self.token.cancel();
+    }
+
+    /// Returns a future that completes once the watcher is stopped.
+    pub fn stopped(&self) -> WaitForCancellation {
+        self.token.cancelled()
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+enum WatchBackend {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Inotify(inotify::Inotify),
+    Polling {
+        root: File,
+        recursive: bool,
+        /// The entries found by the last scan, which is
+        /// `None` until the first scan.
+        snapshot: Option<BTreeMap<String, PollEntry>>,
+    },
+}
+
+impl WatchBackend {
+    async fn changes(&mut self) -> Vec<FileWatchEvent> {
+        match self {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Self::Inotify(inotify) => inotify.read_events(),
+            Self::Polling { root, recursive, snapshot } => {
+                let mut entries = BTreeMap::new();
+                if_native_platform! {{
+                    scan(root, "", *recursive, &mut entries);
+                }}
+                if_browser! {{
+                    scan(root, "", *recursive, &mut entries).await;
+                }}
+                let Some(previous) = snapshot.replace(entries) else {
+                    return vec![];
+                };
+                diff_snapshots(root, &previous, snapshot.as_ref().unwrap())
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct PollEntry {
+    is_directory: bool,
+    modified: Option<std::time::SystemTime>,
+    size: usize,
+}
+
+if_native_platform! {
+    /// Adds the entry at `relative` to `entries` and, if it is a directory,
+    /// its children, which are scanned recursively if `recursive` is true.
+    fn scan(root: &File, relative: &str, recursive: bool, entries: &mut BTreeMap<String, PollEntry>) {
+        let file = resolve_relative(root, relative);
+        if !file.exists() {
+            return;
+        }
+        let is_directory = file.is_directory();
+        let (modified, size) = if is_directory {
+            (None, 0)
+        } else {
+            (file.modification_date().ok().flatten(), file.size().unwrap_or(0))
+        };
+        entries.insert(relative.to_owned(), PollEntry { is_directory, modified, size });
+        if is_directory && (relative.is_empty() || recursive) {
+            for child in file.directory_listing().unwrap_or_default() {
+                scan(root, &join_relative(relative, &child.name()), recursive, entries);
+            }
+        }
+    }
+}
+
+if_browser! {
+    /// Adds the entry at `relative` to `entries` and, if it is a directory,
+    /// its children, which are scanned recursively if `recursive` is true.
+    fn scan<'a>(root: &'a File, relative: &'a str, recursive: bool, entries: &'a mut BTreeMap<String, PollEntry>) -> futures::future::LocalBoxFuture<'a, ()> {
+        Box::pin(async move {
+            let file = resolve_relative(root, relative);
+            if !file.exists_async().await {
+                return;
+            }
+            let is_directory = file.is_directory_async().await;
+            let (modified, size) = if is_directory {
+                (None, 0)
+            } else {
+                (file.modification_date_async().await.ok().flatten(), file.size_async().await.unwrap_or(0))
+            };
+            entries.insert(relative.to_owned(), PollEntry { is_directory, modified, size });
+            if is_directory && (relative.is_empty() || recursive) {
+                for child in file.directory_listing_async().await.unwrap_or_default() {
+                    scan(root, &join_relative(relative, &child.name()), recursive, entries).await;
+                }
+            }
+        })
+    }
+}
+
+fn diff_snapshots(root: &File, previous: &BTreeMap<String, PollEntry>, current: &BTreeMap<String, PollEntry>) -> Vec<FileWatchEvent> {
+    let mut events = vec![];
+    for relative in previous.keys().filter(|relative| !current.contains_key(*relative)) {
+        events.push(FileWatchEvent::Deleted(resolve_relative(root, relative)));
+    }
+    for (relative, entry) in current {
+        match previous.get(relative) {
+            None => events.push(FileWatchEvent::Created(resolve_relative(root, relative))),
+            Some(previous_entry) if !entry.is_directory && previous_entry != entry => {
+                events.push(FileWatchEvent::Modified(resolve_relative(root, relative)));
+            },
+            _ => {},
+        }
+    }
+    events
+}
+
+fn resolve_relative(root: &File, relative: &str) -> File {
+    if relative.is_empty() { root.clone() } else { root.resolve_path(relative) }
+}
+
+fn join_relative(parent: &str, name: &str) -> String {
+    if parent.is_empty() { name.to_owned() } else { format!("{parent}/{name}") }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod inotify {
+    use std::{ffi::CString, io};
+    use super::*;
+
+    const MASK: u32 = libc::IN_CREATE | libc::IN_MODIFY | libc::IN_DELETE | libc::IN_DELETE_SELF | libc::IN_MOVED_FROM | libc::IN_MOVED_TO;
+
+    /// An inotify instance watching a file or a directory tree.
+    pub(super) struct Inotify {
+        fd: i32,
+        root: File,
+        root_path: String,
+        recursive: bool,
+        /// Relative paths of the watched directories by watch descriptor.
+        directories: HashMap<i32, String>,
+    }
+
+    impl Inotify {
+        pub fn new(root: &File, recursive: bool) -> io::Result<Self> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut inotify = Self {
+                fd,
+                root: root.clone(),
+                root_path: root.path_omega(),
+                recursive,
+                directories: HashMap::new(),
+            };
+            inotify.add_watch("")?;
+            if recursive {
+                inotify.add_subdirectories("");
+            }
+            Ok(inotify)
+        }
+
+        fn add_watch(&mut self, relative: &str) -> io::Result<()> {
+            let path = if relative.is_empty() { self.root_path.clone() } else { format!("{}/{relative}", self.root_path) };
+            let path = CString::new(path).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), MASK) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            self.directories.insert(wd, relative.to_owned());
+            Ok(())
+        }
+
+        /// Watches the subdirectories of a watched directory, recursively.
+        fn add_subdirectories(&mut self, relative: &str) {
+            let path = if relative.is_empty() { self.root_path.clone() } else { format!("{}/{relative}", self.root_path) };
+            let Ok(entries) = std::fs::read_dir(path) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                if entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false) {
+                    let child = join_relative(relative, &entry.file_name().to_string_lossy());
+                    if self.add_watch(&child).is_ok() {
+                        self.add_subdirectories(&child);
+                    }
+                }
+            }
+        }
+
+        /// Reads the pending notifications without blocking.
+        pub fn read_events(&mut self) -> Vec<FileWatchEvent> {
+            let mut events = vec![];
+            // Files moved from a watched directory by cookie, with the index
+            // of their `Deleted` event, which a matching move turns into a rename.
+            let mut moved_from: HashMap<u32, (String, bool, usize)> = HashMap::new();
+            let mut modified: HashSet<String> = HashSet::new();
+            let mut buffer = vec![0u8; 16 * 1024];
+            loop {
+                let length = unsafe { libc::read(self.fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+                if length <= 0 {
+                    break;
+                }
+                let mut offset = 0;
+                while offset + std::mem::size_of::<libc::inotify_event>() <= length as usize {
+                    let event = unsafe { std::ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event) };
+                    let name_start = offset + std::mem::size_of::<libc::inotify_event>();
+                    let name = &buffer[name_start..name_start + event.len as usize];
+                    let name = String::from_utf8_lossy(&name[..name.iter().position(|byte| *byte == 0).unwrap_or(name.len())]).into_owned();
+                    offset = name_start + event.len as usize;
+
+                    if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                        // Watch directories created while notifications were lost.
+                        if self.recursive {
+                            self.add_subdirectories("");
+                        }
+                        events.push(FileWatchEvent::Overflow(self.root.clone()));
+                        continue;
+                    }
+                    if event.mask & libc::IN_IGNORED != 0 {
+                        self.directories.remove(&event.wd);
+                        continue;
+                    }
+                    let Some(directory) = self.directories.get(&event.wd).cloned() else {
+                        continue;
+                    };
+                    let relative = if name.is_empty() { directory } else { join_relative(&directory, &name) };
+                    let is_directory = event.mask & libc::IN_ISDIR != 0;
+
+                    if event.mask & libc::IN_CREATE != 0 {
+                        if is_directory && self.recursive && self.add_watch(&relative).is_ok() {
+                            self.add_subdirectories(&relative);
+                        }
+                        events.push(FileWatchEvent::Created(resolve_relative(&self.root, &relative)));
+                    } else if event.mask & libc::IN_MODIFY != 0 {
+                        if modified.insert(relative.clone()) {
+                            events.push(FileWatchEvent::Modified(resolve_relative(&self.root, &relative)));
+                        }
+                    } else if event.mask & libc::IN_DELETE != 0 || (event.mask & libc::IN_DELETE_SELF != 0 && relative.is_empty()) {
+                        events.push(FileWatchEvent::Deleted(resolve_relative(&self.root, &relative)));
+                    } else if event.mask & libc::IN_MOVED_FROM != 0 {
+                        events.push(FileWatchEvent::Deleted(resolve_relative(&self.root, &relative)));
+                        moved_from.insert(event.cookie, (relative, is_directory, events.len() - 1));
+                    } else if event.mask & libc::IN_MOVED_TO != 0 {
+                        match moved_from.remove(&event.cookie) {
+                            Some((from, _, index)) => {
+                                if is_directory {
+                                    self.rename_directories(&from, &relative);
+                                }
+                                events[index] = FileWatchEvent::Renamed {
+                                    from: resolve_relative(&self.root, &from),
+                                    to: resolve_relative(&self.root, &relative),
+                                };
+                            },
+                            None => {
+                                if is_directory && self.recursive && self.add_watch(&relative).is_ok() {
+                                    self.add_subdirectories(&relative);
+                                }
+                                events.push(FileWatchEvent::Created(resolve_relative(&self.root, &relative)));
+                            },
+                        }
+                    }
+                }
+            }
+            // Directories moved out of the watched directory, which
+            // were reported as deleted in the order of the moves.
+            for (from, is_directory, _) in moved_from.into_values() {
+                if is_directory {
+                    self.remove_directories(&from);
+                }
+            }
+            // The watched file or directory was deleted or replaced, such as
+            // by saving through a rename; watch it again once it exists.
+            if !self.directories.values().any(String::is_empty) && self.add_watch("").is_ok() {
+                if self.recursive {
+                    self.add_subdirectories("");
+                }
+                events.push(FileWatchEvent::Created(self.root.clone()));
+            }
+            events
+        }
+
+        /// Stops watching a directory moved out of the watched
+        /// directory and its subdirectories.
+        fn remove_directories(&mut self, relative: &str) {
+            let prefix = format!("{relative}/");
+            let removed: Vec<i32> = self.directories.iter()
+                .filter(|(_, directory)| *directory == relative || directory.starts_with(&prefix))
+                .map(|(wd, _)| *wd)
+                .collect();
+            for wd in removed {
+                unsafe { libc::inotify_rm_watch(self.fd, wd); }
+                self.directories.remove(&wd);
+            }
+        }
+
+        /// Updates the relative paths of watched directories after a move.
+        fn rename_directories(&mut self, from: &str, to: &str) {
+            for relative in self.directories.values_mut() {
+                if relative == from {
+                    *relative = to.to_owned();
+                } else if let Some(rest) = relative.strip_prefix(&format!("{from}/")) {
+                    *relative = format!("{to}/{rest}");
+                }
+            }
+        }
+    }
+
+    impl Drop for Inotify {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.fd); }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::{file::*, timer::{testing::MockClock, Duration}};
+
+    fn collect(watcher: &FileWatcher) -> Arc<Mutex<Vec<String>>> {
+        let events = Arc::new(Mutex::new(vec![]));
+        watcher.on_event().listener({
+            let events = Arc::clone(&events);
+            move |event| events.lock().unwrap().push(match event {
+                FileWatchEvent::Created(file) => format!("created {}", file.name()),
+                FileWatchEvent::Modified(file) => format!("modified {}", file.name()),
+                FileWatchEvent::Deleted(file) => format!("deleted {}", file.name()),
+                FileWatchEvent::Renamed { from, to } => format!("renamed {} {}", from.name(), to.name()),
+                FileWatchEvent::Overflow(file) => format!("overflow {}", file.name()),
+            })
+        });
+        events
+    }
+
+    #[test]
+    fn test_file_watch() {
+        let clock = MockClock::install();
+        let directory = std::env::temp_dir().join(format!("agera-file-watch-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("nested")).unwrap();
+        let root = File::new(&directory.to_string_lossy());
+
+        let watcher = root.watch(true);
+        let polling_watcher = root.watch_polling(true, Duration::from_secs(1));
+        let (events, polled_events) = (collect(&watcher), collect(&polling_watcher));
+        clock.advance(Duration::from_millis(1));
+
+        std::fs::write(directory.join("nested/a.txt"), "a").unwrap();
+        clock.advance(Duration::from_secs(1));
+        std::fs::rename(directory.join("nested/a.txt"), directory.join("b.txt")).unwrap();
+        clock.advance(Duration::from_secs(1));
+        std::fs::remove_file(directory.join("b.txt")).unwrap();
+        clock.advance(Duration::from_secs(1));
+
+        if cfg!(any(target_os = "linux", target_os = "android")) {
+            assert_eq!(*events.lock().unwrap(), ["created a.txt", "modified a.txt", "renamed a.txt b.txt", "deleted b.txt"]);
+        }
+        assert_eq!(*polled_events.lock().unwrap(), ["created a.txt", "deleted a.txt", "created b.txt", "deleted b.txt"]);
+
+        watcher.stop();
+        std::fs::write(directory.join("c.txt"), "c").unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(events.lock().unwrap().len(), 4);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn test_file_watch_move_out() {
+        let clock = MockClock::install();
+        let directory = std::env::temp_dir().join(format!("agera-file-watch-move-out-{}", std::process::id()));
+        let outside = std::env::temp_dir().join(format!("agera-file-watch-outside-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("nested/inner")).unwrap();
+        let root = File::new(&directory.to_string_lossy());
+
+        let watcher = root.watch(true);
+        let events = collect(&watcher);
+        clock.advance(Duration::from_millis(1));
+
+        std::fs::rename(directory.join("nested"), &outside).unwrap();
+        std::fs::write(directory.join("c.txt"), "c").unwrap();
+        clock.advance(Duration::from_secs(1));
+        // Changes within the moved directory are no longer reported.
+        std::fs::write(outside.join("a.txt"), "a").unwrap();
+        std::fs::write(outside.join("inner/b.txt"), "b").unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(*events.lock().unwrap(), ["deleted nested", "created c.txt", "modified c.txt"]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn test_file_watch_replaced_file() {
+        let clock = MockClock::install();
+        let directory = std::env::temp_dir().join(format!("agera-file-watch-replaced-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a.txt"), "a").unwrap();
+        let file = File::new(&directory.join("a.txt").to_string_lossy());
+
+        let watcher = file.watch(false);
+        let events = collect(&watcher);
+        clock.advance(Duration::from_millis(1));
+
+        file.write_atomic("b", AtomicWriteOptions { sync: false, keep_backup: false }).unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(*events.lock().unwrap(), ["deleted a.txt", "created a.txt"]);
+
+        // The replaced file is still watched.
+        std::fs::write(directory.join("a.txt"), "c").unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(*events.lock().unwrap(), ["deleted a.txt", "created a.txt", "modified a.txt"]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -88,7 +88,7 @@ use crate::geom::Vector2d;
 /// 
 /// ![skew][matrix_skew_image.jpg]
 ///
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 #[embed_doc_image("matrix_props1.jpg", "src/geom/docs/assets/matrix_props1.jpg")]
 #[embed_doc_image("matrix_props2.jpg", "src/geom/docs/assets/matrix_props2.jpg")]
 #[embed_doc_image("matrix_translate.jpg", "src/geom/docs/assets/matrix_translate.jpg")]
@@ -1,8 +1,9 @@
 use std::fmt::{Display, Debug};
 use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign};
+use crate::common::*;
 
-/// Represents a two-dimensional vector.
-#[derive(Copy, Clone, PartialEq)]
+/// Represents a two-dimensional vector. Serializes as an `[x, y]` array.
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vector2d(pub f64, pub f64);
 
 impl Debug for Vector2d {
@@ -60,7 +60,9 @@ listener.remove();
 #![feature(structural_match)]
 #![feature(try_blocks)]
 
+pub mod animation;
 pub mod application;
+pub mod binding;
 pub mod common;
 pub mod display;
 pub mod events;
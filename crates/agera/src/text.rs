@@ -1,2 +1,13 @@
 mod style_sheet;
-pub use self::style_sheet::*;
\ No newline at end of file
+pub use self::style_sheet::*;
+
+mod css_parser;
+pub use self::css_parser::CssSyntaxError;
+
+mod font_metrics;
+pub use self::font_metrics::*;
+
+pub(crate) mod layout;
+
+mod measure;
+pub use self::measure::*;
\ No newline at end of file
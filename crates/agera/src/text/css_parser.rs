@@ -0,0 +1,539 @@
+use std::{error::Error, fmt::Display};
+use crate::{common::*, text::*, util::Color};
+
+/// Error resulted from parsing CSS into a `StyleSheetContainer`.
+///
+/// The line and column are one based and locate the
+/// offending selector, property or value.
+#[derive(Clone, Debug)]
+pub struct CssSyntaxError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl CssSyntaxError {
+    /// The one based line at which the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The one based column at which the error occurred.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The error message, without location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for CssSyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for CssSyntaxError {}
+
+/// A selector supported by `StyleSheetContainer::parse_css`.
+enum CssSelector {
+    Host,
+    Anchor,
+    AnchorHover,
+    Selection,
+    HeadingTitle(usize),
+    Class(String),
+}
+
+struct CssDeclaration {
+    property: String,
+    value: String,
+    location: usize,
+}
+
+pub(crate) struct CssParser {
+    source: Vec<char>,
+    index: usize,
+}
+
+impl CssParser {
+    pub fn new(css: &str) -> Self {
+        Self {
+            source: blank_comments(css),
+            index: 0,
+        }
+    }
+
+    /// Parses every rule, applying them over `container`
+    /// in the order they appear.
+    pub fn parse_into(&mut self, container: &mut StyleSheetContainer) -> Result<(), CssSyntaxError> {
+        loop {
+            self.skip_whitespace();
+            if self.at_end() {
+                return Ok(());
+            }
+            let selectors = self.parse_selector_list()?;
+            let declarations = self.parse_declaration_block()?;
+            for selector in selectors {
+                let style = match selector {
+                    CssSelector::Host => &mut container.host,
+                    CssSelector::Anchor => &mut container.anchor,
+                    CssSelector::AnchorHover => &mut container.anchor_hover,
+                    CssSelector::Selection => &mut container.selection,
+                    CssSelector::HeadingTitle(level) => container.heading_title.entry(level).or_default(),
+                    CssSelector::Class(name) => container.classes.entry(name).or_default(),
+                };
+                for declaration in declarations.iter() {
+                    apply_declaration(style, &declaration.property, &declaration.value)
+                        .map_err(|message| self.error_at(declaration.location, message))?;
+                }
+            }
+        }
+    }
+
+    fn parse_selector_list(&mut self) -> Result<Vec<CssSelector>, CssSyntaxError> {
+        let mut selectors = vec![];
+        let mut start = self.index;
+        loop {
+            match self.peek() {
+                None | Some('}') => {
+                    return Err(self.error_at(self.index, "Expected '{' after selector".into()));
+                },
+                Some(ch) if ch == ',' || ch == '{' => {
+                    selectors.push(self.parse_selector(start, self.index)?);
+                    self.index += 1;
+                    if ch == '{' {
+                        return Ok(selectors);
+                    }
+                    start = self.index;
+                },
+                Some(_) => {
+                    self.index += 1;
+                },
+            }
+        }
+    }
+
+    fn parse_selector(&self, start: usize, end: usize) -> Result<CssSelector, CssSyntaxError> {
+        let text = String::from_iter(&self.source[start..end]);
+        let location = start + (text.len() - text.trim_start().len());
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(self.error_at(location, "Expected selector".into()));
+        }
+        match text.to_lowercase().as_ref() {
+            ":host" => return Ok(CssSelector::Host),
+            "a" => return Ok(CssSelector::Anchor),
+            "a:hover" => return Ok(CssSelector::AnchorHover),
+            "::selection" => return Ok(CssSelector::Selection),
+            _ => {},
+        }
+        // Heading levels match the `h1` to `h9` elements of the markup.
+        if let Some((_, level)) = regex_captures!(r"^[hH]([1-9])$", text) {
+            return Ok(CssSelector::HeadingTitle(level.parse().unwrap()));
+        }
+        if let Some((_, name)) = regex_captures!(r"^(?:span)?\.([A-Za-z_\-][A-Za-z0-9_\-]*)$", text) {
+            return Ok(CssSelector::Class(name.to_owned()));
+        }
+        Err(self.error_at(location, format!("Unsupported selector '{text}'")))
+    }
+
+    fn parse_declaration_block(&mut self) -> Result<Vec<CssDeclaration>, CssSyntaxError> {
+        let mut declarations = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => {
+                    return Err(self.error_at(self.index, "Expected '}'".into()));
+                },
+                Some('}') => {
+                    self.index += 1;
+                    return Ok(declarations);
+                },
+                Some(';') => {
+                    self.index += 1;
+                },
+                Some(_) => {
+                    declarations.push(self.parse_declaration()?);
+                },
+            }
+        }
+    }
+
+    fn parse_declaration(&mut self) -> Result<CssDeclaration, CssSyntaxError> {
+        let property_location = self.index;
+        let mut property = String::new();
+        loop {
+            match self.peek() {
+                Some(':') => {
+                    self.index += 1;
+                    break;
+                },
+                None | Some(';') | Some('}') => {
+                    return Err(self.error_at(self.index, "Expected ':' after property name".into()));
+                },
+                Some(ch) => {
+                    property.push(ch);
+                    self.index += 1;
+                },
+            }
+        }
+        let property = property.trim().to_lowercase();
+        if !regex_is_match!(r"^-?[a-z][a-z0-9\-]*$", &property) {
+            return Err(self.error_at(property_location, "Invalid property name".into()));
+        }
+        if !SUPPORTED_PROPERTIES.contains(&property.as_ref()) {
+            return Err(self.error_at(property_location, format!("Unsupported property '{property}'")));
+        }
+
+        self.skip_whitespace();
+        let value_location = self.index;
+        let mut value = String::new();
+        let mut quote: Option<char> = None;
+        loop {
+            match (self.peek(), quote) {
+                (None, Some(_)) => {
+                    return Err(self.error_at(self.index, "Unterminated string".into()));
+                },
+                (None, None) | (Some(';'), None) | (Some('}'), None) => {
+                    break;
+                },
+                (Some(ch), Some(q)) => {
+                    if ch == q {
+                        quote = None;
+                    }
+                    value.push(ch);
+                    self.index += 1;
+                },
+                (Some(ch), None) => {
+                    if ch == '"' || ch == '\'' {
+                        quote = Some(ch);
+                    }
+                    value.push(ch);
+                    self.index += 1;
+                },
+            }
+        }
+
+        Ok(CssDeclaration {
+            property: property.clone(),
+            value: value.trim().to_owned(),
+            location: if value.trim().is_empty() { property_location } else { value_location },
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source.get(self.index).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.index >= self.source.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(|ch| ch.is_whitespace()).unwrap_or(false) {
+            self.index += 1;
+        }
+    }
+
+    fn error_at(&self, index: usize, message: String) -> CssSyntaxError {
+        let mut line = 1;
+        let mut column = 1;
+        for ch in &self.source[..index.min(self.source.len())] {
+            if *ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        CssSyntaxError { line, column, message }
+    }
+}
+
+const SUPPORTED_PROPERTIES: &[&str] = &[
+    "font-size",
+    "font-family",
+    "font-weight",
+    "font-style",
+    "color",
+    "background",
+    "background-color",
+    "text-decoration",
+    "text-transform",
+    "letter-spacing",
+    "line-height",
+    "-agera-leading",
+    "text-indent",
+    "margin-left",
+    "margin-right",
+    "margin-top",
+    "margin-bottom",
+    "-agera-tab-stops",
+    "white-space",
+    "text-align",
+];
+
+/// Replaces comments by whitespace, preserving line breaks so that
+/// error locations still match the original source.
+fn blank_comments(css: &str) -> Vec<char> {
+    let mut chars: Vec<char> = css.chars().collect();
+    let mut i = 0;
+    let mut quote: Option<char> = None;
+    while i < chars.len() {
+        let ch = chars[i];
+        if let Some(q) = quote {
+            if ch == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+        if ch == '"' || ch == '\'' {
+            quote = Some(ch);
+            i += 1;
+            continue;
+        }
+        if ch == '/' && chars.get(i + 1) == Some(&'*') {
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] != '\n' {
+                    chars[i] = ' ';
+                }
+                i += 1;
+            }
+            for _ in 0..2 {
+                if i < chars.len() {
+                    chars[i] = ' ';
+                    i += 1;
+                }
+            }
+            continue;
+        }
+        i += 1;
+    }
+    chars
+}
+
+fn apply_declaration(style: &mut StyleSheet, property: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err(format!("Expected value for property '{property}'"));
+    }
+    let keyword = value.to_lowercase();
+    match property {
+        "font-size" => {
+            style.font_size = Some(parse_length_in_points(value).filter(|size| *size >= 0.0).ok_or_else(|| format!("Invalid font size '{value}'"))?);
+        },
+        "font-family" => {
+            style.font_family = Some(value.to_owned());
+        },
+        "font-weight" => {
+            match keyword.as_ref() {
+                "normal" => {
+                    style.font_weight = Some(400);
+                    style.bold = Some(false);
+                    style.lighter = Some(false);
+                },
+                "bold" | "bolder" => {
+                    style.font_weight = Some(700);
+                    style.bold = Some(true);
+                    style.lighter = Some(false);
+                },
+                "lighter" => {
+                    style.font_weight = Some(300);
+                    style.lighter = Some(true);
+                    style.bold = Some(false);
+                },
+                _ => {
+                    let weight = value.parse::<u32>().ok().filter(|w| (1..=1000).contains(w))
+                        .ok_or_else(|| format!("Invalid font weight '{value}'"))?;
+                    style.font_weight = Some(weight);
+                    style.bold = Some(false);
+                    style.lighter = Some(false);
+                },
+            }
+        },
+        "font-style" => {
+            style.italic = Some(match keyword.as_ref() {
+                "normal" => false,
+                "italic" | "oblique" => true,
+                _ => return Err(format!("Invalid font style '{value}'")),
+            });
+        },
+        "color" => {
+            style.color = Some(parse_color(value)?);
+        },
+        "background" | "background-color" => {
+            style.background_color = Some(parse_color(value)?);
+        },
+        "text-decoration" => {
+            style.text_decoration = Some(match keyword.as_ref() {
+                "none" => TextDecoration::None,
+                "underline" => TextDecoration::Underline,
+                "line-through" => TextDecoration::LineThrough,
+                "overline" => TextDecoration::Overline,
+                _ => return Err(format!("Invalid text decoration '{value}'")),
+            });
+        },
+        "text-transform" => {
+            style.text_transform = Some(match keyword.as_ref() {
+                "none" => TextTransform::None,
+                "capitalize" => TextTransform::Capitalize,
+                "lowercase" => TextTransform::Lowercase,
+                "uppercase" => TextTransform::Uppercase,
+                _ => return Err(format!("Invalid text transform '{value}'")),
+            });
+        },
+        "letter-spacing" => {
+            style.letter_spacing = Some(if keyword == "normal" { 0.0 } else { parse_length(property, value)? });
+        },
+        "line-height" => {
+            style.line_height = Some(if keyword == "normal" {
+                LineHeight::Normal
+            } else if let Some((_, percentage)) = regex_captures!(r"^([0-9]+(?:\.[0-9]+)?)%$", value) {
+                LineHeight::Multiplier(percentage.parse::<f64>().unwrap() / 100.0)
+            } else if let Ok(multiplier) = value.parse::<f64>() {
+                LineHeight::Multiplier(multiplier)
+            } else {
+                LineHeight::Points(parse_length(property, value)?)
+            });
+        },
+        "-agera-leading" => {
+            style.leading = Some(parse_length(property, value)?);
+        },
+        "text-indent" => {
+            style.text_indent = Some(parse_length(property, value)?);
+        },
+        "margin-left" => {
+            style.margin_left = Some(parse_length(property, value)?);
+        },
+        "margin-right" => {
+            style.margin_right = Some(parse_length(property, value)?);
+        },
+        "margin-top" => {
+            style.paragraph_spacing_before = Some(parse_length(property, value)?);
+        },
+        "margin-bottom" => {
+            style.paragraph_spacing_after = Some(parse_length(property, value)?);
+        },
+        "-agera-tab-stops" => {
+            let mut tab_stops = vec![];
+            for stop in value.split_whitespace() {
+                let stop = parse_length(property, stop)?;
+                if tab_stops.last().map(|last| stop <= *last).unwrap_or(false) {
+                    return Err("Tab stops must be in ascending order".into());
+                }
+                tab_stops.push(stop);
+            }
+            style.tab_stops = Some(tab_stops);
+        },
+        "white-space" => {
+            style.white_space = Some(match keyword.as_ref() {
+                "normal" => WhiteSpace::Normal,
+                "nowrap" => WhiteSpace::NoWrap,
+                "pre" => WhiteSpace::Pre,
+                "pre-wrap" => WhiteSpace::PreWrap,
+                "pre-line" => WhiteSpace::PreLine,
+                _ => return Err(format!("Invalid white space '{value}'")),
+            });
+        },
+        "text-align" => {
+            style.text_align = Some(match keyword.as_ref() {
+                "left" | "start" => TextAlign::Left,
+                "center" => TextAlign::Center,
+                "right" | "end" => TextAlign::Right,
+                "justify" => TextAlign::Justify,
+                _ => return Err(format!("Invalid text alignment '{value}'")),
+            });
+        },
+        _ => {
+            return Err(format!("Unsupported property '{property}'"));
+        },
+    }
+    Ok(())
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    value.parse::<Color>().map_err(|_| format!("Invalid color '{value}'"))
+}
+
+fn parse_length(property: &str, value: &str) -> Result<f64, String> {
+    parse_length_in_points(value).ok_or_else(|| format!("Invalid length '{value}' for property '{property}'"))
+}
+
+/// Parses a length in points. Pixel lengths are converted
+/// at 96 pixels per inch, and unitless lengths are taken as points.
+fn parse_length_in_points(value: &str) -> Option<f64> {
+    let value = value.to_lowercase();
+    let (_, number, unit) = regex_captures!(r"^(-?(?:[0-9]+(?:\.[0-9]+)?|\.[0-9]+))(pt|px)?$", &value)?;
+    let number: f64 = number.parse().ok()?;
+    Some(if unit == "px" { number * layout::POINTS_PER_PIXEL } else { number })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{text::*, util::Color};
+
+    #[test]
+    fn test_parse_css() {
+        let container = StyleSheetContainer::parse_css(r#"
+            /* Host */
+            :host { font-size: 12pt; font-family: "Open Sans", sans-serif; color: #000 }
+            a:hover { text-decoration: none }
+            h1, h2 { font-weight: bold; }
+            span.note { font-style: italic; background-color: rgb(255, 255, 0); text-transform: uppercase }
+        "#).unwrap();
+        assert_eq!(container.host.font_size, Some(12.0));
+        assert_eq!(container.host.font_family.as_deref(), Some(r#""Open Sans", sans-serif"#));
+        assert_eq!(container.host.color, Some(Color::new(0.0, 0.0, 0.0, 1.0)));
+        assert_eq!(container.anchor_hover.text_decoration, Some(TextDecoration::None));
+        assert_eq!(container.heading_title[&1].bold, Some(true));
+        assert_eq!(container.heading_title[&2].bold, Some(true));
+        assert_eq!(container.heading_title[&2].font_weight, Some(700));
+        assert_eq!(container.classes["note"].italic, Some(true));
+        assert_eq!(container.classes["note"].text_transform, Some(TextTransform::Uppercase));
+
+        let container = StyleSheetContainer::parse_css(r#"
+            :host { line-height: 150%; letter-spacing: 1px; white-space: pre-wrap; -agera-tab-stops: 36pt 72pt }
+            h1 { text-align: justify; margin-left: 10pt; margin-bottom: 6pt; text-indent: -4pt; text-decoration: line-through }
+        "#).unwrap();
+        assert_eq!(container.host.line_height, Some(LineHeight::Multiplier(1.5)));
+        assert_eq!(container.host.letter_spacing, Some(0.75));
+        assert_eq!(container.host.white_space, Some(WhiteSpace::PreWrap));
+        assert_eq!(container.host.tab_stops, Some(vec![36.0, 72.0]));
+        assert_eq!(container.heading_title[&1].text_align, Some(TextAlign::Justify));
+        assert_eq!(container.heading_title[&1].margin_left, Some(10.0));
+        assert_eq!(container.heading_title[&1].paragraph_spacing_after, Some(6.0));
+        assert_eq!(container.heading_title[&1].text_indent, Some(-4.0));
+        assert_eq!(container.heading_title[&1].text_decoration, Some(TextDecoration::LineThrough));
+
+        // Defaults are preserved for selectors that are not mentioned.
+        assert_eq!(container.selection.color, Some(Color::new(1.0, 1.0, 1.0, 1.0)));
+
+        // Later font weights override earlier ones.
+        let container = StyleSheetContainer::parse_css(r#"
+            .a { font-weight: bold; font-weight: normal }
+            .b { font-weight: 300; font-weight: bold }
+        "#).unwrap();
+        assert_eq!((container.classes["a"].font_weight, container.classes["a"].bold), (Some(400), Some(false)));
+        assert_eq!((container.classes["b"].font_weight, container.classes["b"].bold), (Some(700), Some(true)));
+    }
+
+    #[test]
+    fn test_parse_css_errors() {
+        let error = StyleSheetContainer::parse_css(":host {\n  colour: red;\n}").err().unwrap();
+        assert_eq!((error.line(), error.column()), (2, 3));
+
+        let error = StyleSheetContainer::parse_css("a { color: nocolor }").err().unwrap();
+        assert_eq!((error.line(), error.column()), (1, 12));
+
+        let error = StyleSheetContainer::parse_css("div { color: red }").err().unwrap();
+        assert_eq!((error.line(), error.column()), (1, 1));
+
+        let error = StyleSheetContainer::parse_css("h1 { color: red }\nh10 { color: red }").err().unwrap();
+        assert_eq!((error.line(), error.column()), (2, 1));
+
+        assert!(StyleSheetContainer::parse_css(":host { color: red").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::{Arc, RwLock};
+use crate::common::*;
+
+/// Describes a font as resolved from a `StyleSheet`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FontDescription {
+    /// Font family, possibly including comma-separated fallback fonts.
+    pub family: String,
+    /// Font size, in points.
+    pub size: f64,
+    /// Font weight, between 1 and 1000. 400 is normal and 700 is bold.
+    pub weight: u32,
+    pub italic: bool,
+}
+
+/// Provides glyph measurements to the text layout engine.
+///
+/// The layout engine uses [`ApproximateFontMetrics`] by default.
+/// Platforms that can measure fonts precisely install their own
+/// implementation through [`set_font_metrics`].
+pub trait FontMetrics: Send + Sync {
+    /// Horizontal advance of a character, in points.
+    fn advance_width(&self, character: char, font: &FontDescription) -> f64;
+
+    /// Distance from the baseline to the top of the line box, in points.
+    fn ascent(&self, font: &FontDescription) -> f64;
+
+    /// Distance from the baseline to the bottom of the line box, in points.
+    fn descent(&self, font: &FontDescription) -> f64;
+}
+
+/// Font metrics derived solely from the font size, using typical
+/// proportions of sans-serif and monospace fonts.
+pub struct ApproximateFontMetrics;
+
+impl FontMetrics for ApproximateFontMetrics {
+    fn advance_width(&self, character: char, font: &FontDescription) -> f64 {
+        if font.family.to_lowercase().contains("mono") {
+            return font.size * 0.6;
+        }
+        let ratio = match character {
+            ' ' | '\u{A0}' | 'i' | 'j' | 'l' | 'I' | '.' | ',' | ':' | ';' | '\'' | '|' | '!' => 0.28,
+            'f' | 't' | 'r' | '(' | ')' | '[' | ']' | '-' => 0.35,
+            'm' | 'w' | 'M' | 'W' => 0.83,
+            'A'..='Z' => 0.67,
+            '0'..='9' => 0.55,
+            '\u{2E80}'.. => 1.0,
+            _ => 0.5,
+        };
+        let weight_ratio = if font.weight >= 600 { 1.05 } else { 1.0 };
+        font.size * ratio * weight_ratio
+    }
+
+    fn ascent(&self, font: &FontDescription) -> f64 {
+        font.size * 0.9
+    }
+
+    fn descent(&self, font: &FontDescription) -> f64 {
+        font.size * 0.25
+    }
+}
+
+static FONT_METRICS: Lazy<RwLock<Arc<dyn FontMetrics>>> = Lazy::new(|| RwLock::new(Arc::new(ApproximateFontMetrics)));
+
+/// Replaces the font metrics used by the text layout engine.
+pub fn set_font_metrics(metrics: impl FontMetrics + 'static) {
+    *FONT_METRICS.write().unwrap() = Arc::new(metrics);
+}
+
+/// The font metrics used by the text layout engine.
+pub fn font_metrics() -> Arc<dyn FontMetrics> {
+    Arc::clone(&FONT_METRICS.read().unwrap())
+}
@@ -0,0 +1,732 @@
+/*!
+The text layout engine, which breaks styled paragraphs into positioned lines.
+
+All lengths are in points. The vertical axis points downwards, with
+the origin at the top left corner of the layout.
+*/
+
+use std::{ops::Range, sync::Arc};
+use crate::{common::*, geom::Vector2d, text::*};
+
+pub(crate) mod markup;
+use self::markup::{BaselineShift, ImageAlign, InlineItem, Markup, Paragraph};
+
+/// Font size used when no style specifies one, in points.
+pub(crate) const DEFAULT_FONT_SIZE: f64 = 12.0;
+
+/// Font family used when no style specifies one.
+pub(crate) const DEFAULT_FONT_FAMILY: &str = "sans-serif";
+
+/// Points per pixel, at 96 pixels per inch. Display objects are sized in
+/// pixels, while text is laid out in points.
+pub(crate) const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// Distance between default tab stops, in points.
+pub(crate) const DEFAULT_TAB_WIDTH: f64 = 36.0;
+
+/// Indentation added for each level of list nesting, in points.
+pub(crate) const LIST_INDENT: f64 = 18.0;
+
+pub(crate) struct LayoutOptions {
+    /// The width available for lines. `None` disables wrapping.
+    pub max_width: Option<f64>,
+    /// Paragraph alignment used when a paragraph style does not set `text_align`.
+    pub align: TextAlign,
+    /// The `href` of the anchor under the pointer, styled with `anchor_hover`.
+    pub hovered_href: Option<String>,
+    /// Natural sizes of the loaded images, by source. Images that
+    /// are not loaded are sized by their attributes only.
+    pub image_sizes: HashMap<String, Vector2d>,
+}
+
+// Drawing fields are read by platform renderers.
+#[allow(dead_code)]
+pub(crate) struct TextLayout {
+    pub lines: Vec<LayoutLine>,
+    /// The plain text content the line ranges refer to.
+    pub text: String,
+    /// The horizontal extent of the laid out content.
+    pub width: f64,
+    pub height: f64,
+    /// Inline and floating images.
+    pub images: Vec<LayoutImage>,
+}
+
+#[allow(dead_code)]
+pub(crate) struct LayoutLine {
+    /// Top of the line box.
+    pub y: f64,
+    pub height: f64,
+    /// Position of the baseline, from the top of the layout.
+    pub baseline: f64,
+    pub ascent: f64,
+    pub descent: f64,
+    /// Left edge of the line content.
+    pub x: f64,
+    /// Width of the line content, excluding trailing spaces.
+    pub width: f64,
+    /// Byte range in the plain text.
+    pub text_range: Range<usize>,
+    pub fragments: Vec<LayoutFragment>,
+    pub horizontal_rule: bool,
+}
+
+#[allow(dead_code)]
+pub(crate) struct LayoutFragment {
+    /// The text to draw, after text transforms.
+    pub text: String,
+    pub x: f64,
+    pub width: f64,
+    /// Vertical offset from the line baseline, positive downwards.
+    pub baseline_offset: f64,
+    pub style: Arc<StyleSheet>,
+    pub font: FontDescription,
+    /// Byte range in the plain text. List markers have an empty range.
+    pub text_range: Range<usize>,
+    pub href: Option<String>,
+}
+
+#[allow(dead_code)]
+pub(crate) struct LayoutImage {
+    pub source: String,
+    /// Left edge of the image.
+    pub x: f64,
+    /// Top edge of the image.
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Byte range of the image in the plain text.
+    pub text_range: Range<usize>,
+    pub href: Option<String>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum AtomKind {
+    Word,
+    Space,
+    Tab,
+    LineBreak,
+    /// An inline image.
+    Image,
+    /// A floating image, which takes no space in its line.
+    Float,
+}
+
+struct AtomImage {
+    source: String,
+    height: f64,
+    align: ImageAlign,
+}
+
+struct Atom {
+    kind: AtomKind,
+    text: String,
+    width: f64,
+    ascent: f64,
+    descent: f64,
+    font_size: f64,
+    baseline_offset: f64,
+    style: Arc<StyleSheet>,
+    font: FontDescription,
+    text_range: Range<usize>,
+    href: Option<String>,
+    image: Option<AtomImage>,
+}
+
+/// Lines and images laid out so far, along with the floats
+/// text wraps around.
+struct LayoutState {
+    lines: Vec<LayoutLine>,
+    images: Vec<LayoutImage>,
+    floats: Vec<Float>,
+}
+
+/// Lays out HTML text.
+pub(crate) fn layout_html(html: &str, style_sheet: &StyleSheetContainer, options: &LayoutOptions) -> TextLayout {
+    layout(markup::parse(html, style_sheet, options.hovered_href.as_deref()), options)
+}
+
+/// Lays out parsed markup.
+pub(crate) fn layout(markup: Markup, options: &LayoutOptions) -> TextLayout {
+    let metrics = font_metrics();
+    let mut state = LayoutState {
+        lines: vec![],
+        images: vec![],
+        floats: vec![],
+    };
+    let mut y = 0.0;
+    for paragraph in markup.paragraphs.iter() {
+        y += paragraph.style.paragraph_spacing_before.unwrap_or(0.0);
+        y = layout_paragraph(paragraph, options, &*metrics, y, &mut state);
+        y += paragraph.style.paragraph_spacing_after.unwrap_or(0.0);
+    }
+    let width = state.lines.iter()
+        .flat_map(|line| line.fragments.iter().map(|fragment| fragment.x + fragment.width))
+        .chain(state.images.iter().map(|image| image.x + image.width))
+        .fold(0.0, f64::max);
+    let height = state.floats.iter().map(|float| float.y + float.height).fold(y, f64::max);
+    TextLayout {
+        lines: state.lines,
+        text: markup.text,
+        width,
+        height,
+        images: state.images,
+    }
+}
+
+pub(crate) fn resolve_font(style: &StyleSheet, shift: BaselineShift) -> FontDescription {
+    let weight = if style.bold == Some(true) {
+        700
+    } else if style.lighter == Some(true) {
+        300
+    } else {
+        style.font_weight.unwrap_or(400)
+    };
+    let size = style.font_size.unwrap_or(DEFAULT_FONT_SIZE);
+    FontDescription {
+        family: style.font_family.clone().unwrap_or(DEFAULT_FONT_FAMILY.into()),
+        size: if shift == BaselineShift::None { size } else { size * 0.7 },
+        weight,
+        italic: style.italic.unwrap_or(false),
+    }
+}
+
+fn transform_text(text: &str, transform: TextTransform, at_word_start: bool) -> String {
+    match transform {
+        TextTransform::None => text.to_owned(),
+        TextTransform::Lowercase => text.to_lowercase(),
+        TextTransform::Uppercase => text.to_uppercase(),
+        TextTransform::Capitalize => {
+            let mut chars = text.chars();
+            match chars.next() {
+                Some(first) if at_word_start => first.to_uppercase().chain(chars).collect(),
+                _ => text.to_owned(),
+            }
+        },
+    }
+}
+
+fn measure_text(text: &str, font: &FontDescription, letter_spacing: f64, metrics: &dyn FontMetrics) -> f64 {
+    text.chars().map(|ch| metrics.advance_width(ch, font) + letter_spacing).sum()
+}
+
+fn atoms_of(paragraph: &Paragraph, options: &LayoutOptions, metrics: &dyn FontMetrics) -> Vec<Atom> {
+    let mut atoms = vec![];
+    let mut at_word_start = true;
+    for item in paragraph.items.iter() {
+        match item {
+            InlineItem::LineBreak { style, start } => {
+                let font = resolve_font(style, BaselineShift::None);
+                atoms.push(Atom {
+                    kind: AtomKind::LineBreak,
+                    text: String::new(),
+                    width: 0.0,
+                    ascent: metrics.ascent(&font),
+                    descent: metrics.descent(&font),
+                    font_size: font.size,
+                    baseline_offset: 0.0,
+                    style: Arc::clone(style),
+                    font,
+                    text_range: *start..(*start + 1),
+                    href: None,
+                    image: None,
+                });
+                at_word_start = true;
+            },
+            InlineItem::Image { source, width, height, align, style, start, href } => {
+                let font = resolve_font(style, BaselineShift::None);
+                let size = image_size(*width, *height, options.image_sizes.get(source).cloned());
+                atoms.push(Atom {
+                    kind: if *align == ImageAlign::Inline { AtomKind::Image } else { AtomKind::Float },
+                    text: String::new(),
+                    width: size.x(),
+                    ascent: size.y(),
+                    descent: 0.0,
+                    font_size: font.size,
+                    baseline_offset: 0.0,
+                    style: Arc::clone(style),
+                    font,
+                    text_range: *start..(*start + '\u{FFFC}'.len_utf8()),
+                    href: href.clone(),
+                    image: Some(AtomImage {
+                        source: source.clone(),
+                        height: size.y(),
+                        align: *align,
+                    }),
+                });
+                at_word_start = false;
+            },
+            InlineItem::Text { text, style, start, href, shift } => {
+                let font = resolve_font(style, *shift);
+                let base_size = style.font_size.unwrap_or(DEFAULT_FONT_SIZE);
+                let baseline_offset = match shift {
+                    BaselineShift::None => 0.0,
+                    BaselineShift::Superscript => -base_size * 0.35,
+                    BaselineShift::Subscript => base_size * 0.2,
+                };
+                let letter_spacing = style.letter_spacing.unwrap_or(0.0);
+                let transform = style.text_transform.unwrap_or(TextTransform::None);
+                let mut segment_start = 0;
+                for segment in split_segments(text) {
+                    let kind = match segment {
+                        "\t" => AtomKind::Tab,
+                        _ if segment.starts_with(' ') => AtomKind::Space,
+                        _ => AtomKind::Word,
+                    };
+                    let segment_text = if kind == AtomKind::Word { transform_text(segment, transform, at_word_start) } else { segment.to_owned() };
+                    at_word_start = kind != AtomKind::Word;
+                    let range = (start + segment_start)..(start + segment_start + segment.len());
+                    segment_start += segment.len();
+                    atoms.push(Atom {
+                        kind,
+                        width: if kind == AtomKind::Tab { 0.0 } else { measure_text(&segment_text, &font, letter_spacing, metrics) },
+                        text: segment_text,
+                        ascent: metrics.ascent(&font) - baseline_offset,
+                        descent: metrics.descent(&font) + baseline_offset,
+                        font_size: font.size,
+                        baseline_offset,
+                        style: Arc::clone(style),
+                        font: font.clone(),
+                        text_range: range,
+                        href: href.clone(),
+                        image: None,
+                    });
+                }
+            },
+        }
+    }
+    atoms
+}
+
+/// Splits text into words, single spaces and tabs.
+fn split_segments(text: &str) -> Vec<&str> {
+    let mut segments = vec![];
+    let mut word_start: Option<usize> = None;
+    for (i, ch) in text.char_indices() {
+        if ch == ' ' || ch == '\t' {
+            if let Some(start) = word_start.take() {
+                segments.push(&text[start..i]);
+            }
+            segments.push(&text[i..(i + 1)]);
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+    if let Some(start) = word_start {
+        segments.push(&text[start..]);
+    }
+    segments
+}
+
+fn next_tab_stop(position: f64, tab_stops: &[f64]) -> f64 {
+    if let Some(stop) = tab_stops.iter().find(|stop| **stop > position + 0.001) {
+        return *stop;
+    }
+    let last_stop = tab_stops.last().copied().unwrap_or(0.0).max(0.0);
+    last_stop + (((position - last_stop) / DEFAULT_TAB_WIDTH).floor() + 1.0) * DEFAULT_TAB_WIDTH
+}
+
+/// A floating image, which text wraps around.
+struct Float {
+    left: bool,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Returns the left and right edges available to a line at `y`,
+/// excluding floats.
+fn line_edges(floats: &[Float], y: f64, margin_left: f64, right_edge: f64) -> (f64, f64) {
+    let mut left = margin_left;
+    let mut right = right_edge;
+    for float in floats.iter().filter(|float| y >= float.y && y < float.y + float.height) {
+        if float.left {
+            left = left.max(float.x + float.width);
+        } else {
+            right = right.min(float.x);
+        }
+    }
+    (left, right)
+}
+
+/// Resolves the size of an image from its attributes and natural size.
+/// Dimensions that cannot be resolved yet, before the image loads, are zero.
+fn image_size(width: Option<f64>, height: Option<f64>, natural_size: Option<Vector2d>) -> Vector2d {
+    match (width, height, natural_size) {
+        (Some(width), Some(height), _) => Vector2d(width, height),
+        (Some(width), None, Some(natural)) if natural.x() > 0.0 => Vector2d(width, width * natural.y() / natural.x()),
+        (None, Some(height), Some(natural)) if natural.y() > 0.0 => Vector2d(height * natural.x() / natural.y(), height),
+        (None, None, Some(natural)) => natural,
+        (width, height, _) => Vector2d(width.unwrap_or(0.0), height.unwrap_or(0.0)),
+    }
+}
+
+/// A line produced by line breaking, before positioning its fragments.
+struct BrokenLine {
+    atoms: Vec<Atom>,
+    break_atom: Option<Atom>,
+    y: f64,
+    left: f64,
+    right: f64,
+}
+
+impl BrokenLine {
+    /// Returns the ascent, descent and height of the line box.
+    fn line_box(&self, style: &StyleSheet, paragraph_font: &FontDescription, metrics: &dyn FontMetrics) -> (f64, f64, f64) {
+        let mut ascent: f64 = 0.0;
+        let mut descent: f64 = 0.0;
+        let mut max_font_size: f64 = 0.0;
+        for atom in self.atoms.iter().chain(self.break_atom.iter()) {
+            ascent = ascent.max(atom.ascent);
+            descent = descent.max(atom.descent);
+            max_font_size = max_font_size.max(atom.font_size);
+        }
+        if self.atoms.is_empty() && self.break_atom.is_none() {
+            ascent = metrics.ascent(paragraph_font);
+            descent = metrics.descent(paragraph_font);
+            max_font_size = paragraph_font.size;
+        }
+        let height = match style.line_height.unwrap_or(LineHeight::Normal) {
+            LineHeight::Normal => ascent + descent,
+            LineHeight::Multiplier(multiplier) => multiplier * max_font_size,
+            LineHeight::Points(points) => points,
+        };
+        (ascent, descent, height)
+    }
+}
+
+/// Lays out a paragraph starting at `y`, returning the bottom of its last line.
+fn layout_paragraph(paragraph: &Paragraph, options: &LayoutOptions, metrics: &dyn FontMetrics, mut y: f64, state: &mut LayoutState) -> f64 {
+    let style = &paragraph.style;
+    let paragraph_font = resolve_font(style, BaselineShift::None);
+    let margin_left = style.margin_left.unwrap_or(0.0) + paragraph.list_depth as f64 * LIST_INDENT;
+    let margin_right = style.margin_right.unwrap_or(0.0);
+    let white_space = style.white_space.unwrap_or(WhiteSpace::Normal);
+    let wraps = options.max_width.is_some() && ![WhiteSpace::NoWrap, WhiteSpace::Pre].contains(&white_space);
+    let collapses = ![WhiteSpace::Pre, WhiteSpace::PreWrap].contains(&white_space);
+    let right_edge = options.max_width.map(|width| width - margin_right).unwrap_or(f64::INFINITY);
+    let align = style.text_align.unwrap_or(options.align);
+    let tab_stops = style.tab_stops.clone().unwrap_or(vec![]);
+    let leading = style.leading.unwrap_or(0.0);
+    let text_indent = style.text_indent.unwrap_or(0.0);
+
+    if paragraph.horizontal_rule {
+        let height = paragraph_font.size;
+        state.lines.push(LayoutLine {
+            y,
+            height,
+            baseline: y + height / 2.0,
+            ascent: height / 2.0,
+            descent: height / 2.0,
+            x: margin_left,
+            width: if right_edge.is_finite() { (right_edge - margin_left).max(0.0) } else { 0.0 },
+            text_range: paragraph.start..paragraph.start,
+            fragments: vec![],
+            horizontal_rule: true,
+        });
+        return y + height;
+    }
+
+    // Break atoms into lines. Floats met in the middle of a line
+    // are placed at the top of the next line.
+    let atoms = atoms_of(paragraph, options, metrics);
+    let mut broken_lines: Vec<BrokenLine> = vec![];
+    let mut current: Vec<Atom> = vec![];
+    let mut pending_floats: Vec<Atom> = vec![];
+    let (mut left, mut right) = line_edges(&state.floats, y, margin_left, right_edge);
+    let mut x = left + text_indent;
+
+    let place_float = |atom: Atom, y: f64, state: &mut LayoutState| {
+        let image = atom.image.unwrap();
+        let size = Vector2d(atom.width, image.height);
+        let (left, right) = line_edges(&state.floats, y, margin_left, right_edge);
+        let float_left = image.align != ImageAlign::Right || !right.is_finite();
+        let float_x = if float_left { left } else { right - size.x() };
+        state.floats.push(Float { left: float_left, x: float_x, y, width: size.x(), height: size.y() });
+        state.images.push(LayoutImage {
+            source: image.source,
+            x: float_x,
+            y,
+            width: size.x(),
+            height: size.y(),
+            text_range: atom.text_range,
+            href: atom.href,
+        });
+    };
+
+    let finish_line = |broken_lines: &mut Vec<BrokenLine>, current: &mut Vec<Atom>, forced_break: bool, line_y: &mut f64, pending_floats: &mut Vec<Atom>, (left, right): (f64, f64), state: &mut LayoutState| {
+        let mut atoms = std::mem::take(current);
+        let break_atom = if forced_break { atoms.pop() } else { None };
+        while atoms.last().map(|atom| atom.kind == AtomKind::Space).unwrap_or(false) {
+            atoms.pop();
+        }
+        let line = BrokenLine { atoms, break_atom, y: *line_y, left, right };
+        let (_, _, height) = line.line_box(style, &paragraph_font, metrics);
+        *line_y += height + leading;
+        broken_lines.push(line);
+        for float in pending_floats.drain(..) {
+            place_float(float, *line_y, state);
+        }
+    };
+
+    let mut line_y = y;
+    for mut atom in atoms {
+        let is_first_line = broken_lines.is_empty();
+        match atom.kind {
+            AtomKind::Float => {
+                if current.iter().any(|atom| [AtomKind::Word, AtomKind::Image].contains(&atom.kind)) {
+                    pending_floats.push(atom);
+                } else {
+                    place_float(atom, line_y, state);
+                    let indent = x - left;
+                    (left, right) = line_edges(&state.floats, line_y, margin_left, right_edge);
+                    x = left + indent;
+                }
+                continue;
+            },
+            AtomKind::LineBreak => {
+                current.push(atom);
+                finish_line(&mut broken_lines, &mut current, true, &mut line_y, &mut pending_floats, (left, right), state);
+            },
+            AtomKind::Space => {
+                if collapses && current.iter().all(|atom| atom.kind == AtomKind::LineBreak) && !is_first_line {
+                    continue;
+                }
+                x += atom.width;
+                current.push(atom);
+                continue;
+            },
+            AtomKind::Tab => {
+                let position = x - left;
+                atom.width = next_tab_stop(position, &tab_stops) - position;
+                x += atom.width;
+                current.push(atom);
+                continue;
+            },
+            AtomKind::Word | AtomKind::Image => {
+                let has_content = current.iter().any(|atom| [AtomKind::Word, AtomKind::Image].contains(&atom.kind));
+                if wraps && has_content && x + atom.width > right {
+                    finish_line(&mut broken_lines, &mut current, false, &mut line_y, &mut pending_floats, (left, right), state);
+                    (left, right) = line_edges(&state.floats, line_y, margin_left, right_edge);
+                    x = left;
+                }
+                x += atom.width;
+                current.push(atom);
+                continue;
+            },
+        }
+        (left, right) = line_edges(&state.floats, line_y, margin_left, right_edge);
+        x = left;
+    }
+    if !current.is_empty() || broken_lines.last().map(|line| line.break_atom.is_some()).unwrap_or(true) {
+        finish_line(&mut broken_lines, &mut current, false, &mut line_y, &mut pending_floats, (left, right), state);
+    }
+
+    let line_count = broken_lines.len();
+    for (line_index, line) in broken_lines.into_iter().enumerate() {
+        let is_first = line_index == 0;
+        let is_last = line_index + 1 == line_count;
+        let start_x = line.left + if is_first { text_indent } else { 0.0 };
+        let (ascent, descent, height) = line.line_box(style, &paragraph_font, metrics);
+        let BrokenLine { atoms, break_atom, y: line_y, right, .. } = line;
+        let forced_break = break_atom.is_some();
+
+        let content_width: f64 = atoms.iter().map(|atom| atom.width).sum();
+        let available = right - start_x;
+        let space_count = atoms.iter().filter(|atom| atom.kind == AtomKind::Space).count();
+        let justify = align == TextAlign::Justify && wraps && !is_last && !forced_break && space_count != 0;
+        let offset = if !available.is_finite() || justify {
+            0.0
+        } else {
+            match align {
+                TextAlign::Left | TextAlign::Justify => 0.0,
+                TextAlign::Center => ((available - content_width) / 2.0).max(0.0),
+                TextAlign::Right => (available - content_width).max(0.0),
+            }
+        };
+        let extra_space = if justify { ((available - content_width) / space_count as f64).max(0.0) } else { 0.0 };
+        let baseline = line_y + (height - (ascent + descent)) / 2.0 + ascent;
+
+        let text_start = atoms.first().map(|atom| atom.text_range.start)
+            .or(break_atom.as_ref().map(|atom| atom.text_range.start))
+            .unwrap_or(paragraph.start);
+        let text_end = atoms.last().map(|atom| atom.text_range.end).unwrap_or(text_start);
+
+        let mut fragments = vec![];
+        if is_first {
+            if let Some(marker) = paragraph.list_marker.as_ref() {
+                let width = measure_text(marker, &paragraph_font, 0.0, metrics);
+                fragments.push(LayoutFragment {
+                    text: marker.clone(),
+                    x: margin_left - width - paragraph_font.size * 0.5,
+                    width,
+                    baseline_offset: 0.0,
+                    style: Arc::clone(style),
+                    font: paragraph_font.clone(),
+                    text_range: paragraph.start..paragraph.start,
+                    href: None,
+                });
+            }
+        }
+        let mut fragment_x = start_x + offset;
+        for atom in atoms {
+            let width = atom.width + if atom.kind == AtomKind::Space { extra_space } else { 0.0 };
+            if let Some(image) = atom.image {
+                state.images.push(LayoutImage {
+                    source: image.source,
+                    x: fragment_x,
+                    y: baseline - image.height,
+                    width,
+                    height: image.height,
+                    text_range: atom.text_range.clone(),
+                    href: atom.href.clone(),
+                });
+            }
+            fragments.push(LayoutFragment {
+                text: atom.text,
+                x: fragment_x,
+                width,
+                baseline_offset: atom.baseline_offset,
+                style: atom.style,
+                font: atom.font,
+                text_range: atom.text_range,
+                href: atom.href,
+            });
+            fragment_x += width;
+        }
+
+        state.lines.push(LayoutLine {
+            y: line_y,
+            height,
+            baseline,
+            ascent,
+            descent,
+            x: start_x + offset,
+            width: fragment_x - (start_x + offset),
+            text_range: text_start..text_end,
+            fragments,
+            horizontal_rule: false,
+        });
+        y = line_y + height;
+    }
+    y
+}
+
+impl TextLayout {
+    /// Returns the fragment at `position`, if any.
+    pub fn fragment_at(&self, position: Vector2d) -> Option<&LayoutFragment> {
+        let line = self.lines.iter().find(|line| position.y() >= line.y && position.y() < line.y + line.height)?;
+        line.fragments.iter().find(|fragment| position.x() >= fragment.x && position.x() < fragment.x + fragment.width)
+    }
+
+    /// Returns the image at `position`, if any.
+    pub fn image_at(&self, position: Vector2d) -> Option<&LayoutImage> {
+        self.images.iter().find(|image| {
+            position.x() >= image.x && position.x() < image.x + image.width
+                && position.y() >= image.y && position.y() < image.y + image.height
+        })
+    }
+
+    /// Returns the `href` of the anchor at `position`, if any,
+    /// including anchors around images.
+    pub fn href_at(&self, position: Vector2d) -> Option<&str> {
+        if let Some(image) = self.image_at(position) {
+            return image.href.as_deref();
+        }
+        self.fragment_at(position).and_then(|fragment| fragment.href.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout_with(html: &str, css: &str, max_width: Option<f64>) -> TextLayout {
+        let style_sheet = StyleSheetContainer::parse_css(css).unwrap();
+        layout_html(html, &style_sheet, &LayoutOptions { max_width, align: TextAlign::Left, hovered_href: None, image_sizes: HashMap::new() })
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let layout = layout_with("<p>one two three four five six</p>", ":host { font-family: monospace; font-size: 10pt }", Some(60.0));
+        let lines: Vec<&str> = layout.lines.iter().map(|line| &layout.text[line.text_range.clone()]).collect();
+        assert_eq!(lines, ["one two", "three four", "five six"]);
+        assert!(layout.width <= 60.0);
+
+        let layout = layout_with("<p>one two three four five six</p>", ":host { font-family: monospace; font-size: 10pt; white-space: nowrap }", Some(60.0));
+        assert_eq!(layout.lines.len(), 1);
+    }
+
+    #[test]
+    fn test_paragraph_properties() {
+        let css = ":host { font-family: monospace; font-size: 10pt; line-height: 2; margin-left: 5pt; text-indent: 12pt; margin-bottom: 4pt }";
+        let layout = layout_with("<p>aa bb</p><p>cc</p>", css, Some(200.0));
+        assert_eq!(layout.lines.len(), 2);
+        assert_eq!(layout.lines[0].height, 20.0);
+        assert_eq!(layout.lines[0].x, 17.0);
+        assert_eq!(layout.lines[1].y, 24.0);
+        assert_eq!(layout.height, 48.0);
+
+        let layout = layout_with("a\tb", ":host { font-family: monospace; font-size: 10pt; white-space: pre; -agera-tab-stops: 40pt }", None);
+        assert_eq!(layout.lines[0].fragments[2].x, 40.0);
+    }
+
+    #[test]
+    fn test_justify() {
+        let css = ":host { font-family: monospace; font-size: 10pt; text-align: justify }";
+        let layout = layout_with("aa bb cc dd ee ff gg", css, Some(100.0));
+        assert_eq!(layout.lines.len(), 2);
+        let first = &layout.lines[0];
+        let last_fragment = first.fragments.last().unwrap();
+        assert!((last_fragment.x + last_fragment.width - 100.0).abs() < 0.001);
+        let second = &layout.lines[1];
+        assert_eq!(second.width, 30.0);
+    }
+
+    #[test]
+    fn test_white_space() {
+        let layout = layout_with("a  b\nc<br>d", "", None);
+        assert_eq!(layout.text, "a b c\nd");
+        assert_eq!(layout.lines.len(), 2);
+
+        let layout = layout_with("a  b\nc", ":host { white-space: pre }", None);
+        assert_eq!(layout.text, "a  b\nc");
+        assert_eq!(layout.lines.len(), 2);
+    }
+
+    #[test]
+    fn test_images() {
+        let css = ":host { font-family: monospace; font-size: 10pt }";
+        let layout = layout_with(r#"ab<img src="app://a.png" width="20" height="30">cd"#, css, None);
+        assert_eq!(layout.text, "ab\u{FFFC}cd");
+        assert_eq!(layout.lines[0].fragments[2].x, 32.0);
+        assert_eq!(layout.images[0].x, 12.0);
+        assert_eq!(layout.images[0].height, 30.0);
+        assert_eq!(layout.lines[0].ascent, 30.0);
+
+        // Unknown dimensions are taken from the natural size.
+        let style_sheet = StyleSheetContainer::parse_css(css).unwrap();
+        let options = LayoutOptions {
+            max_width: None,
+            align: TextAlign::Left,
+            hovered_href: None,
+            image_sizes: hashmap! { "app://a.png".to_owned() => Vector2d(40.0, 20.0) },
+        };
+        let layout = layout_html(r#"<img src="app://a.png" width="20"><img src="app://a.png">"#, &style_sheet, &options);
+        assert_eq!((layout.images[0].width, layout.images[0].height), (20.0, 10.0));
+        assert_eq!((layout.images[1].width, layout.images[1].height), (40.0, 20.0));
+
+        // Text wraps around floating images.
+        let layout = layout_with(r#"<img src="app://a.png" align="left" width="30" height="25">aa bb cc dd ee ff gg"#, css, Some(60.0));
+        assert_eq!(layout.images[0].x, 0.0);
+        assert_eq!(layout.lines[0].x, 30.0);
+        assert_eq!(layout.lines[2].x, 30.0);
+        assert_eq!(layout.lines[3].x, 0.0);
+
+        let layout = layout_with(r#"aa<img src="app://a.png" align="right" width="30" height="25"> bb cc dd ee"#, css, Some(60.0));
+        assert_eq!((layout.images[0].x, layout.images[0].y), (30.0, layout.lines[1].y));
+        assert!(layout.lines[1].x + layout.lines[1].width <= 30.0);
+        assert_eq!(layout.height, layout.lines[1].y + 25.0);
+    }
+}
\ No newline at end of file
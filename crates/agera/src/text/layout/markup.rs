@@ -0,0 +1,457 @@
+/*!
+Parses the HTML dialect supported by `TextField` into paragraphs
+of styled inline items.
+*/
+
+use std::sync::Arc;
+use crate::{common::*, text::*};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum BaselineShift {
+    None,
+    Superscript,
+    Subscript,
+}
+
+/// Placement of an inline image, given by its `align` attribute.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum ImageAlign {
+    /// The image flows with the text, sitting on the baseline.
+    Inline,
+    /// The image floats to the left edge and text wraps around it.
+    Left,
+    /// The image floats to the right edge and text wraps around it.
+    Right,
+}
+
+pub(crate) enum InlineItem {
+    Text {
+        text: String,
+        style: Arc<StyleSheet>,
+        /// Byte offset in the plain text.
+        start: usize,
+        href: Option<String>,
+        shift: BaselineShift,
+    },
+    LineBreak {
+        style: Arc<StyleSheet>,
+        start: usize,
+    },
+    /// An image, represented by U+FFFC in the plain text.
+    Image {
+        source: String,
+        /// Width given by the `width` attribute.
+        width: Option<f64>,
+        /// Height given by the `height` attribute.
+        height: Option<f64>,
+        align: ImageAlign,
+        style: Arc<StyleSheet>,
+        start: usize,
+        href: Option<String>,
+    },
+}
+
+pub(crate) struct Paragraph {
+    /// The style of the block, used for paragraph properties
+    /// such as margins and alignment.
+    pub style: Arc<StyleSheet>,
+    pub items: Vec<InlineItem>,
+    pub list_depth: usize,
+    pub list_marker: Option<String>,
+    pub horizontal_rule: bool,
+    /// Byte offset in the plain text.
+    pub start: usize,
+}
+
+pub(crate) struct Markup {
+    pub paragraphs: Vec<Paragraph>,
+    /// The plain text content, with paragraphs and line breaks
+    /// delimited by line feeds.
+    pub text: String,
+}
+
+struct OpenElement {
+    name: String,
+    style: StyleSheet,
+    href: Option<String>,
+    shift: BaselineShift,
+}
+
+struct MarkupParser<'a> {
+    style_sheet: &'a StyleSheetContainer,
+    hovered_href: Option<&'a str>,
+    open_elements: Vec<OpenElement>,
+    lists: Vec<(bool, usize)>,
+    pending_list_marker: Option<String>,
+    paragraph: Option<Paragraph>,
+    /// Whether the last character emitted to the current paragraph
+    /// is collapsible whitespace or the paragraph start.
+    collapsed_space: bool,
+    markup: Markup,
+}
+
+const BLOCK_ELEMENTS: &[&str] = &["p", "li", "ul", "ol", "center", "h1", "h2", "h3", "h4", "h5", "h6", "h7", "h8", "h9"];
+
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img"];
+
+/// Parses HTML text into paragraphs. Anchors whose `href` equals
+/// `hovered_href` are given the `anchor_hover` style.
+pub(crate) fn parse(html: &str, style_sheet: &StyleSheetContainer, hovered_href: Option<&str>) -> Markup {
+    let mut parser = MarkupParser {
+        style_sheet,
+        hovered_href,
+        open_elements: vec![],
+        lists: vec![],
+        pending_list_marker: None,
+        paragraph: None,
+        collapsed_space: true,
+        markup: Markup {
+            paragraphs: vec![],
+            text: String::new(),
+        },
+    };
+    let chars: Vec<char> = html.chars().collect();
+    let mut i = 0;
+    let mut text = String::new();
+    while i < chars.len() {
+        if chars[i] == '<' {
+            if let Some((tag, next_index)) = parse_tag(&chars, i) {
+                parser.add_text(&unescape_html(&text));
+                text.clear();
+                parser.process_tag(tag);
+                i = next_index;
+                continue;
+            }
+        }
+        text.push(chars[i]);
+        i += 1;
+    }
+    parser.add_text(&unescape_html(&text));
+    parser.finish_paragraph();
+    parser.markup
+}
+
+/// Extracts the plain text content of HTML text.
+pub(crate) fn plain_text(html: &str) -> String {
+    parse(html, &StyleSheetContainer::default(), None).text
+}
+
+/// Extracts the sources of the images of HTML text, in order of appearance.
+pub(crate) fn image_sources(html: &str) -> Vec<String> {
+    parse(html, &StyleSheetContainer::default(), None).paragraphs.into_iter()
+        .flat_map(|paragraph| paragraph.items)
+        .filter_map(|item| match item {
+            InlineItem::Image { source, .. } => Some(source),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses a `width` or `height` attribute, given in pixels.
+fn parse_dimension(value: &str) -> Option<f64> {
+    value.trim().trim_end_matches("px").parse::<f64>().ok().filter(|value| *value >= 0.0)
+}
+
+enum Tag {
+    Open {
+        name: String,
+        attributes: HashMap<String, String>,
+    },
+    Close {
+        name: String,
+    },
+    Comment,
+}
+
+fn parse_tag(chars: &[char], start: usize) -> Option<(Tag, usize)> {
+    let mut i = start + 1;
+    if chars[i..].starts_with(&['!', '-', '-']) {
+        while i < chars.len() && !chars[i..].starts_with(&['-', '-', '>']) {
+            i += 1;
+        }
+        return Some((Tag::Comment, (i + 3).min(chars.len())));
+    }
+    let closing = chars.get(i) == Some(&'/');
+    if closing {
+        i += 1;
+    }
+    if !chars.get(i).map(|ch| ch.is_ascii_alphabetic()).unwrap_or(false) {
+        return None;
+    }
+    let mut name = String::new();
+    while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+        name.push(chars[i].to_ascii_lowercase());
+        i += 1;
+    }
+    let mut attributes = HashMap::new();
+    loop {
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        match chars.get(i) {
+            None => return None,
+            Some('>') => {
+                i += 1;
+                break;
+            },
+            Some('/') => {
+                i += 1;
+            },
+            Some(_) => {
+                let mut attribute_name = String::new();
+                while i < chars.len() && !chars[i].is_whitespace() && !['=', '>', '/'].contains(&chars[i]) {
+                    attribute_name.push(chars[i].to_ascii_lowercase());
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                let mut value = String::new();
+                if chars.get(i) == Some(&'=') {
+                    i += 1;
+                    while i < chars.len() && chars[i].is_whitespace() {
+                        i += 1;
+                    }
+                    match chars.get(i) {
+                        Some(quote) if *quote == '"' || *quote == '\'' => {
+                            let quote = *quote;
+                            i += 1;
+                            while i < chars.len() && chars[i] != quote {
+                                value.push(chars[i]);
+                                i += 1;
+                            }
+                            i += 1;
+                        },
+                        _ => {
+                            while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '>' {
+                                value.push(chars[i]);
+                                i += 1;
+                            }
+                        },
+                    }
+                }
+                attributes.insert(attribute_name, unescape_html(&value));
+            },
+        }
+    }
+    Some((if closing { Tag::Close { name } } else { Tag::Open { name, attributes } }, i))
+}
+
+impl<'a> MarkupParser<'a> {
+    fn process_tag(&mut self, tag: Tag) {
+        match tag {
+            Tag::Comment => {},
+            Tag::Open { name, attributes } => {
+                match name.as_ref() {
+                    "br" => {
+                        let style = self.computed_style();
+                        self.paragraph();
+                        let start = self.markup.text.len();
+                        self.paragraph().items.push(InlineItem::LineBreak { style, start });
+                        self.markup.text.push('\n');
+                        self.collapsed_space = true;
+                        return;
+                    },
+                    "hr" => {
+                        self.finish_paragraph();
+                        self.paragraph().horizontal_rule = true;
+                        self.finish_paragraph();
+                        return;
+                    },
+                    "img" => {
+                        self.add_image(&attributes);
+                        return;
+                    },
+                    _ => {},
+                }
+                if VOID_ELEMENTS.contains(&name.as_ref()) {
+                    return;
+                }
+                let is_block = BLOCK_ELEMENTS.contains(&name.as_ref());
+                if is_block {
+                    self.finish_paragraph();
+                }
+                let mut element = OpenElement {
+                    name: name.clone(),
+                    style: default(),
+                    href: None,
+                    shift: BaselineShift::None,
+                };
+                match name.as_ref() {
+                    "b" => element.style.bold = Some(true),
+                    "i" => element.style.italic = Some(true),
+                    "u" => element.style.text_decoration = Some(TextDecoration::Underline),
+                    "s" => element.style.text_decoration = Some(TextDecoration::LineThrough),
+                    "sup" => element.shift = BaselineShift::Superscript,
+                    "sub" => element.shift = BaselineShift::Subscript,
+                    "center" => element.style.text_align = Some(TextAlign::Center),
+                    "a" => {
+                        element.style = self.style_sheet.anchor.clone();
+                        element.href = attributes.get("href").cloned();
+                        if element.href.is_some() && element.href.as_deref() == self.hovered_href {
+                            element.style.apply(&self.style_sheet.anchor_hover);
+                        }
+                    },
+                    "ul" => self.lists.push((false, 0)),
+                    "ol" => self.lists.push((true, 0)),
+                    "li" => {
+                        self.pending_list_marker = Some(match self.lists.last_mut() {
+                            Some((true, counter)) => {
+                                *counter += 1;
+                                format!("{counter}.")
+                            },
+                            _ => "\u{2022}".into(),
+                        });
+                    },
+                    _ => {
+                        if let Some((_, level)) = regex_captures!(r"^h([1-9])$", &name) {
+                            if let Some(style) = self.style_sheet.heading_title.get(&level.parse::<usize>().unwrap()) {
+                                element.style = style.clone();
+                            }
+                        }
+                    },
+                }
+                if let Some(class_names) = attributes.get("class") {
+                    for class_name in class_names.split_whitespace() {
+                        if let Some(style) = self.style_sheet.classes.get(class_name) {
+                            element.style.apply(style);
+                        }
+                    }
+                }
+                self.open_elements.push(element);
+            },
+            Tag::Close { name } => {
+                let Some(index) = self.open_elements.iter().rposition(|element| element.name == name) else {
+                    return;
+                };
+                let closes_block = self.open_elements[index..].iter().any(|element| BLOCK_ELEMENTS.contains(&element.name.as_ref()));
+                if closes_block {
+                    self.finish_paragraph();
+                }
+                for element in self.open_elements.drain(index..) {
+                    if element.name == "ul" || element.name == "ol" {
+                        self.lists.pop();
+                    }
+                }
+            },
+        }
+    }
+
+    fn computed_style(&self) -> Arc<StyleSheet> {
+        let mut style = self.style_sheet.host.clone();
+        for element in self.open_elements.iter() {
+            style.apply(&element.style);
+        }
+        Arc::new(style)
+    }
+
+    fn paragraph(&mut self) -> &mut Paragraph {
+        if self.paragraph.is_none() {
+            if !self.markup.paragraphs.is_empty() {
+                self.markup.text.push('\n');
+            }
+            self.paragraph = Some(Paragraph {
+                style: self.computed_style(),
+                items: vec![],
+                list_depth: self.lists.len(),
+                list_marker: self.pending_list_marker.take(),
+                horizontal_rule: false,
+                start: self.markup.text.len(),
+            });
+            self.collapsed_space = true;
+        }
+        self.paragraph.as_mut().unwrap()
+    }
+
+    fn finish_paragraph(&mut self) {
+        if let Some(paragraph) = self.paragraph.take() {
+            self.markup.paragraphs.push(paragraph);
+        }
+    }
+
+    fn add_image(&mut self, attributes: &HashMap<String, String>) {
+        let Some(source) = attributes.get("src") else {
+            return;
+        };
+        let style = self.computed_style();
+        let href = self.open_elements.iter().rev().find_map(|element| element.href.clone());
+        let align = match attributes.get("align").map(|align| align.to_lowercase()).as_deref() {
+            Some("left") => ImageAlign::Left,
+            Some("right") => ImageAlign::Right,
+            _ => ImageAlign::Inline,
+        };
+        self.paragraph();
+        let start = self.markup.text.len();
+        self.markup.text.push('\u{FFFC}');
+        self.collapsed_space = false;
+        self.paragraph().items.push(InlineItem::Image {
+            source: source.clone(),
+            width: attributes.get("width").and_then(|width| parse_dimension(width)),
+            height: attributes.get("height").and_then(|height| parse_dimension(height)),
+            align,
+            style,
+            start,
+            href,
+        });
+    }
+
+    fn add_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let style = self.computed_style();
+        let white_space = style.white_space.unwrap_or(WhiteSpace::Normal);
+        let preserves_spaces = [WhiteSpace::Pre, WhiteSpace::PreWrap].contains(&white_space);
+        let preserves_line_breaks = preserves_spaces || white_space == WhiteSpace::PreLine;
+
+        // Whitespace between blocks does not open a paragraph.
+        if self.paragraph.is_none() && !preserves_spaces && text.trim().is_empty() {
+            return;
+        }
+
+        let href = self.open_elements.iter().rev().find_map(|element| element.href.clone());
+        let shift = self.open_elements.iter().rev().map(|element| element.shift).find(|shift| *shift != BaselineShift::None).unwrap_or(BaselineShift::None);
+
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let lines: Vec<&str> = if preserves_line_breaks { text.split('\n').collect() } else { vec![&text] };
+        for (i, line) in lines.iter().enumerate() {
+            if i != 0 {
+                self.paragraph();
+                let start = self.markup.text.len();
+                self.paragraph().items.push(InlineItem::LineBreak { style: Arc::clone(&style), start });
+                self.markup.text.push('\n');
+                self.collapsed_space = true;
+            }
+            let mut content = String::new();
+            if preserves_spaces {
+                content.push_str(line);
+            } else {
+                let line = if preserves_line_breaks { line.trim_matches(|ch: char| ch.is_whitespace()) } else { line };
+                for ch in line.chars() {
+                    if ch.is_whitespace() && ch != '\u{A0}' {
+                        if !self.collapsed_space {
+                            content.push(' ');
+                            self.collapsed_space = true;
+                        }
+                    } else {
+                        content.push(ch);
+                        self.collapsed_space = false;
+                    }
+                }
+            }
+            if content.is_empty() {
+                continue;
+            }
+            self.paragraph();
+            let start = self.markup.text.len();
+            self.markup.text.push_str(&content);
+            self.paragraph().items.push(InlineItem::Text {
+                text: content,
+                style: Arc::clone(&style),
+                start,
+                href: href.clone(),
+                shift,
+            });
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::ops::Range;
+use crate::{common::*, text::{*, layout::*}};
+
+/// Measurements of laid out text, as returned by [`measure`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextMetrics {
+    /// The plain text content, which line ranges refer to. Paragraphs and
+    /// line breaks are delimited by line feeds, and images are represented
+    /// by U+FFFC.
+    pub text: String,
+    /// The width of the widest line, in points.
+    pub width: f64,
+    /// The total height, in points.
+    pub height: f64,
+    /// The ascent of the first line, in points.
+    pub ascent: f64,
+    /// The descent of the last line, in points.
+    pub descent: f64,
+    pub lines: Vec<LineMetrics>,
+}
+
+/// Measurements of a single line of text.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LineMetrics {
+    /// Byte range in the plain text of `TextMetrics`.
+    pub text_range: Range<usize>,
+    /// Left edge of the line content, in points.
+    pub x: f64,
+    /// Top of the line box, in points.
+    pub y: f64,
+    /// Width of the line content, excluding trailing spaces, in points.
+    pub width: f64,
+    pub height: f64,
+    /// Position of the baseline, from the top of the text, in points.
+    pub baseline: f64,
+    pub ascent: f64,
+    pub descent: f64,
+}
+
+impl TextMetrics {
+    /// The number of lines.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The text of a line.
+    pub fn line_text(&self, index: usize) -> &str {
+        &self.text[self.lines[index].text_range.clone()]
+    }
+}
+
+/// Measures HTML text, in the dialect supported by `TextField`, without
+/// creating a display object. Lines wrap at `max_width`, if given.
+/// 
+/// Images are sized by their `width` and `height` attributes only,
+/// since they are not loaded.
+/// 
+/// ```ignore
+/// use agera::text::*;
+/// let style_sheet = StyleSheetContainer::parse_css(":host { font-size: 14pt }").unwrap();
+/// let metrics = measure("Hello <b>world</b>", &style_sheet, Some(200.0));
+/// println!("{} lines, {} points tall", metrics.line_count(), metrics.height);
+/// ```
+pub fn measure(html: &str, style_sheet: &StyleSheetContainer, max_width: Option<f64>) -> TextMetrics {
+    let layout = layout_html(html, style_sheet, &LayoutOptions {
+        max_width,
+        align: TextAlign::Left,
+        hovered_href: None,
+        image_sizes: HashMap::new(),
+    });
+    TextMetrics {
+        width: layout.lines.iter().map(|line| line.x + line.width).fold(0.0, f64::max),
+        height: layout.height,
+        ascent: layout.lines.first().map(|line| line.ascent).unwrap_or(0.0),
+        descent: layout.lines.last().map(|line| line.descent).unwrap_or(0.0),
+        lines: layout.lines.iter().map(|line| LineMetrics {
+            text_range: line.text_range.clone(),
+            x: line.x,
+            y: line.y,
+            width: line.width,
+            height: line.height,
+            baseline: line.baseline,
+            ascent: line.ascent,
+            descent: line.descent,
+        }).collect(),
+        text: layout.text,
+    }
+}
+
+/// Measures plain text, where line feeds are taken as line breaks.
+/// This is equivalent to measuring the HTML assigned by `TextField::set_text()`.
+pub fn measure_plain_text(text: &str, style_sheet: &StyleSheetContainer, max_width: Option<f64>) -> TextMetrics {
+    measure(&plain_text_to_html(text), style_sheet, max_width)
+}
+
+/// Converts plain text into HTML, taking line feeds as line breaks.
+pub(crate) fn plain_text_to_html(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::*;
+
+    #[test]
+    fn test_measure() {
+        let style_sheet = StyleSheetContainer::parse_css(":host { font-family: monospace; font-size: 10pt }").unwrap();
+        let metrics = measure("one two three", &style_sheet, Some(50.0));
+        assert_eq!(metrics.line_count(), 2);
+        assert_eq!(metrics.line_text(0), "one two");
+        assert_eq!(metrics.line_text(1), "three");
+        assert_eq!(metrics.width, 42.0);
+        assert_eq!(metrics.ascent, 9.0);
+        assert_eq!(metrics.descent, 2.5);
+        assert_eq!(metrics.height, 23.0);
+
+        let metrics = measure_plain_text("a < b\nc", &style_sheet, None);
+        assert_eq!(metrics.text, "a < b\nc");
+        assert_eq!(metrics.line_count(), 2);
+    }
+}
@@ -1,6 +1,9 @@
-use crate::{common::*, util::Color};
+use crate::{common::*, text::CssSyntaxError, util::Color};
 
 /// Text formatting rules for font size, color, and other styles.
+///
+/// A `StyleSheetContainer` can be constructed from CSS through
+/// [`StyleSheetContainer::parse_css`].
 #[derive(Clone)]
 pub struct StyleSheetContainer {
     /// The host style. This is equivalent to the CSS `:host` selector.
@@ -11,8 +14,11 @@ pub struct StyleSheetContainer {
     pub anchor_hover: StyleSheet,
     /// Selection style. This is equivalent to the CSS `::selection` selector.
     pub selection: StyleSheet,
-    /// Heading title styles.
+    /// Heading title styles by level, from 1 to 9.
     pub heading_title: HashMap<usize, StyleSheet>,
+    /// Class styles, applying to `<span class="name">`. This is equivalent to the
+    /// CSS `.name` and `span.name` selectors.
+    pub classes: HashMap<String, StyleSheet>,
 }
 
 impl Default for StyleSheetContainer {
@@ -30,10 +36,56 @@ impl Default for StyleSheetContainer {
                 ..
             },
             heading_title: hashmap! {},
+            classes: hashmap! {},
         }
     }
 }
 
+impl StyleSheetContainer {
+    /// Parses CSS into a `StyleSheetContainer`. Rules are applied
+    /// in order over the default container.
+    ///
+    /// The following selectors are supported, optionally in comma-separated lists:
+    ///
+    /// | Selector              | Field            |
+    /// | --------------------- | ---------------- |
+    /// | `:host`               | `host`           |
+    /// | `a`                   | `anchor`         |
+    /// | `a:hover`             | `anchor_hover`   |
+    /// | `::selection`         | `selection`      |
+    /// | `h1` to `h9`          | `heading_title`  |
+    /// | `.name`, `span.name`  | `classes`        |
+    ///
+    /// The supported properties are `font-size`, `font-family`,
+    /// `font-weight`, `font-style`, `color`, `background`, `background-color`,
+    /// `text-decoration`, `text-transform`, `letter-spacing`, `line-height`,
+    /// `text-indent`, `margin-left`, `margin-right`, `margin-top`, `margin-bottom`,
+    /// `white-space` and `text-align`, plus the Agera extensions `-agera-leading`
+    /// and `-agera-tab-stops`. Lengths are given in `pt` or `px` and colors are
+    /// parsed as [`Color`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` with the line and column of the first unsupported selector,
+    /// unsupported property, invalid value or malformed rule.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use agera::text::*;
+    /// let style_sheet = StyleSheetContainer::parse_css(r#"
+    ///     :host { font-size: 12pt; color: #333 }
+    ///     a:hover { text-decoration: underline }
+    ///     .warning { color: orange; font-weight: bold }
+    /// "#).unwrap();
+    /// ```
+    pub fn parse_css(css: &str) -> Result<StyleSheetContainer, CssSyntaxError> {
+        let mut container = StyleSheetContainer::default();
+        super::css_parser::CssParser::new(css).parse_into(&mut container)?;
+        Ok(container)
+    }
+}
+
 /// Style applicable to text fields.
 #[derive(Clone)]
 pub struct StyleSheet {
@@ -68,6 +120,76 @@ pub struct StyleSheet {
     pub text_decoration: Option<TextDecoration>,
 
     pub text_transform: Option<TextTransform>,
+
+    /// Additional space after each character, in points.
+    /// This is equivalent to the CSS property `letter-spacing`.
+    pub letter_spacing: Option<f64>,
+
+    /// Height of line boxes.
+    /// This is equivalent to the CSS property `line-height`.
+    pub line_height: Option<LineHeight>,
+
+    /// Additional vertical space between lines of a paragraph, in points.
+    /// This is equivalent to the CSS property `-agera-leading`.
+    pub leading: Option<f64>,
+
+    /// Indentation of the first line of a paragraph, in points.
+    /// This is equivalent to the CSS property `text-indent`.
+    pub text_indent: Option<f64>,
+
+    /// Left margin of a paragraph, in points.
+    /// This is equivalent to the CSS property `margin-left`.
+    pub margin_left: Option<f64>,
+
+    /// Right margin of a paragraph, in points.
+    /// This is equivalent to the CSS property `margin-right`.
+    pub margin_right: Option<f64>,
+
+    /// Vertical space before a paragraph, in points.
+    /// This is equivalent to the CSS property `margin-top`.
+    pub paragraph_spacing_before: Option<f64>,
+
+    /// Vertical space after a paragraph, in points.
+    /// This is equivalent to the CSS property `margin-bottom`.
+    pub paragraph_spacing_after: Option<f64>,
+
+    /// Tab stop positions relative to the left margin, in points and
+    /// in ascending order. Tabs past the last stop advance to multiples
+    /// of the default tab width.
+    /// This is equivalent to the CSS property `-agera-tab-stops`.
+    pub tab_stops: Option<Vec<f64>>,
+
+    /// Whitespace collapsing and wrapping.
+    /// This is equivalent to the CSS property `white-space`.
+    pub white_space: Option<WhiteSpace>,
+
+    /// Paragraph alignment. If set, overrides the horizontal alignment
+    /// of the text field.
+    /// This is equivalent to the CSS property `text-align`.
+    pub text_align: Option<TextAlign>,
+}
+
+impl StyleSheet {
+    /// Overrides the fields of this style with the fields
+    /// that are set in `other`.
+    pub(crate) fn apply(&mut self, other: &StyleSheet) {
+        macro_rules! apply_fields {
+            ($($field:ident),*) => {
+                $(
+                    if other.$field.is_some() {
+                        self.$field = other.$field.clone();
+                    }
+                )*
+            };
+        }
+        apply_fields!(
+            font_size, font_family, font_weight, lighter, bold, italic, color,
+            background_color, text_decoration, text_transform, letter_spacing,
+            line_height, leading, text_indent, margin_left, margin_right,
+            paragraph_spacing_before, paragraph_spacing_after, tab_stops,
+            white_space, text_align
+        );
+    }
 }
 
 impl Default for StyleSheet {
@@ -83,20 +205,70 @@ impl Default for StyleSheet {
             background_color: None,
             text_decoration: None,
             text_transform: None,
+            letter_spacing: None,
+            line_height: None,
+            leading: None,
+            text_indent: None,
+            margin_left: None,
+            margin_right: None,
+            paragraph_spacing_before: None,
+            paragraph_spacing_after: None,
+            tab_stops: None,
+            white_space: None,
+            text_align: None,
         }
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum TextDecoration {
     None,
     Underline,
+    LineThrough,
+    Overline,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum TextTransform {
     None,
     Capitalize,
     Lowercase,
     Uppercase,
+}
+
+/// Height of line boxes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LineHeight {
+    /// Line height derived from the font ascent and descent.
+    Normal,
+    /// Line height as a multiple of the font size.
+    Multiplier(f64),
+    /// Line height in points.
+    Points(f64),
+}
+
+/// Whitespace collapsing and wrapping, equivalent to the values
+/// of the CSS `white-space` property.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WhiteSpace {
+    /// Collapses whitespace and wraps lines.
+    Normal,
+    /// Collapses whitespace and does not wrap lines.
+    NoWrap,
+    /// Preserves whitespace and line breaks and does not wrap lines.
+    Pre,
+    /// Preserves whitespace and line breaks and wraps lines.
+    PreWrap,
+    /// Collapses spaces, preserves line breaks and wraps lines.
+    PreLine,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    /// Stretches spaces so that every line of a paragraph, except for the last
+    /// and lines ending in an explicit break, fills the available width.
+    Justify,
 }
\ No newline at end of file
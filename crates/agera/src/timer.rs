@@ -1,12 +1,29 @@
 /*!
 Work with timing and ticking.
+
+Actions can be scheduled at wall-clock times through cron expressions
+(`free_cron`) or dates (`free_at`).
+
+Timed logic can be tested deterministically by installing
+a mock clock from the `testing` submodule.
 */
 
 pub use std::time::Duration;
-use std::{ops::{Add, AddAssign, Sub, SubAssign}, sync::{Arc, RwLock}};
+use std::{ops::{Add, AddAssign, Sub, SubAssign}, pin::pin};
 use crate::{platforms::{if_native_platform, if_browser}, common::*};
 
-mod target;
+mod platforms;
+
+pub mod testing;
+
+mod cancellation;
+pub use self::cancellation::*;
+
+mod ticker;
+pub use self::ticker::*;
+
+mod schedule;
+pub use self::schedule::*;
 
 /// A measurement of a monotonically nondecreasing clock. Opaque and useful only with `Duration`.
 /// 
@@ -20,7 +37,7 @@ mod target;
 /// 
 #[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
 pub struct Instant {
-    inner: target::Instant,
+    inner: platforms::Instant,
 }
 
 impl Instant {
@@ -30,9 +47,13 @@ impl Instant {
         self.inner.since(other.inner)
     }
 
-    /// Returns the current instant from the host environment.
+    /// Returns the current instant from the host environment,
+    /// or from the mock clock installed on the current thread.
     pub fn now() -> Instant {
-        Self { inner: target::Instant::now() }
+        if let Some(clock) = testing::current() {
+            return clock.now();
+        }
+        Self { inner: platforms::Instant::now() }
     }
 
     /// Adds a duration to the instant, returning a new instant.
@@ -83,22 +104,6 @@ impl SubAssign<Duration> for Instant {
     }
 }
 
-/// Ticker returned by [`ticker`],
-/// [`ticker_at`], [`animation_ticker`] and
-/// [`animation_ticker_at`].
-#[derive(Debug)]
-pub struct Ticker {
-    inner: target::Ticker,
-}
-
-impl Ticker {
-    /// Completes when the next instant in the ticker has been reached,
-    /// yielding the time elapsed since the last tick.
-    pub async fn tick(&mut self) -> Duration {
-        self.inner.tick().await
-    }
-}
-
 /// Asynchronously waits until `duration` has elapsed.
 ///
 /// Equivalent to `wait_until(Instant::now() + duration)`.
@@ -113,8 +118,9 @@ impl Ticker {
 /// 
 /// # Cancellation
 ///
-/// Canceling a wait being awaited for via the `.await` operator is not possible.
-/// Use [`free_timeout`] for such a purpose.
+/// A wait is cancelled by dropping its future. To wait until either
+/// the duration elapses or a token is cancelled, use [`wait_cancellable`];
+/// to limit how long another future is awaited, use [`timeout`].
 /// 
 /// # Examples
 /// 
@@ -130,12 +136,16 @@ impl Ticker {
 /// ```
 /// 
 pub async fn wait(duration: Duration) {
+    if let Some(clock) = testing::current() {
+        clock.wait_until(clock.now() + duration, false).await;
+        return;
+    }
     if_native_platform! {{
         future::no_send!();
         tokio::time::sleep(duration).await;
     }}
     if_browser! {{
-        target::browser::wait(duration).await;
+        platforms::browser::wait(duration).await;
     }}
 }
 
@@ -151,8 +161,9 @@ pub async fn wait(duration: Duration) {
 ///
 /// # Cancellation
 ///
-/// Canceling a wait being awaited for via the `.await` operator is not possible.
-/// Use [`free_timeout`] for such a purpose.
+/// A wait is cancelled by dropping its future. To wait until either
+/// the duration elapses or a token is cancelled, use [`wait_cancellable`];
+/// to limit how long another future is awaited, use [`timeout`].
 /// 
 /// # Examples
 /// 
@@ -168,12 +179,16 @@ pub async fn wait(duration: Duration) {
 /// ```
 /// 
 pub async fn wait_until(deadline: Instant) {
+    if let Some(clock) = testing::current() {
+        clock.wait_until(deadline, false).await;
+        return;
+    }
     if_native_platform! {{
         future::no_send!();
         tokio::time::sleep_until(deadline.inner.0).await;
     }}
     if_browser! {{
-        target::browser::wait(deadline.since(Instant::now())).await;
+        platforms::browser::wait(deadline.since(Instant::now())).await;
     }}
 }
 
@@ -243,22 +258,7 @@ pub async fn wait_until(deadline: Instant) {
 /// [`.tick().await`]: Ticker::tick
 ///
 pub fn ticker(period: Duration) -> Ticker {
-    if_native_platform! {{
-        return Ticker {
-            inner: target::native::Ticker(tokio::time::interval(period)),
-        };
-    }}
-    if_browser! {{
-        assert!(period.as_millis() != 0, "agera::timer::ticker() must be called with non-zero period");
-        return Ticker {
-            inner: target::browser::Ticker {
-                for_animation: false,
-                period,
-                start: Instant::now(),
-                ticker: None,
-            },
-        };
-    }}
+    Ticker::new(Instant::now(), period, false)
 }
 
 /// Creates a new [`Ticker`] that yields with ticker of `period` with the
@@ -297,22 +297,7 @@ pub fn ticker(period: Duration) -> Ticker {
 /// ```
 /// 
 pub fn ticker_at(start: Instant, period: Duration) -> Ticker {
-    if_native_platform! {{
-        return Ticker {
-            inner: target::native::Ticker(tokio::time::interval_at(start.inner.0, period)),
-        };
-    }}
-    if_browser! {{
-        assert!(period.as_millis() != 0, "agera::timer::ticker_at() must be called with non-zero period");
-        return Ticker {
-            inner: target::browser::Ticker {
-                for_animation: false,
-                period,
-                start: start,
-                ticker: None,
-            },
-        };
-    }}
+    Ticker::new(start, period, false)
 }
 
 /// Creates a new [`Ticker`] that yields with ticker of `period`. The first
@@ -348,22 +333,7 @@ pub fn ticker_at(start: Instant, period: Duration) -> Ticker {
 /// [`.tick().await`]: Ticker::tick
 ///
 pub fn animation_ticker(period: Duration) -> Ticker {
-    if_native_platform! {{
-        return Ticker {
-            inner: target::native::Ticker(tokio::time::interval(period)),
-        };
-    }}
-    if_browser! {{
-        assert!(period.as_millis() != 0, "agera::timer::ticker() must be called with non-zero period");
-        return Ticker {
-            inner: target::browser::Ticker {
-                for_animation: true,
-                period,
-                start: Instant::now(),
-                ticker: None,
-            },
-        };
-    }}
+    Ticker::new(Instant::now(), period, true)
 }
 
 /// Creates a new [`Ticker`] that yields with ticker of `period` with the
@@ -397,22 +367,7 @@ pub fn animation_ticker(period: Duration) -> Ticker {
 /// ```
 /// 
 pub fn animation_ticker_at(start: Instant, period: Duration) -> Ticker {
-    if_native_platform! {{
-        return Ticker {
-            inner: target::native::Ticker(tokio::time::interval_at(start.inner.0, period)),
-        };
-    }}
-    if_browser! {{
-        assert!(period.as_millis() != 0, "agera::timer::ticker_at() must be called with non-zero period");
-        return Ticker {
-            inner: target::browser::Ticker {
-                for_animation: true,
-                period,
-                start: start,
-                ticker: None,
-            },
-        };
-    }}
+    Ticker::new(start, period, true)
 }
 
 /// Executes an action after some elapsed time. This macro
@@ -435,18 +390,17 @@ pub macro free_timeout {
 
 #[doc(hidden)]
 pub fn free_timeout(callback: Box<(dyn Fn() + Send + Sync + 'static)>, duration: Duration) -> FreeTimeout {
-    let mut stopped = Arc::new(RwLock::new(false));
+    let token = CancellationToken::new();
     future::exec({
-        let stopped = Arc::clone(&mut stopped);
+        let token = token.clone();
         async move {
-            wait(duration).await;
-            if !*stopped.read().unwrap() {
+            if wait_cancellable(duration, &token).await.is_ok() {
                 callback();
             }
         }
     });
     FreeTimeout {
-        stopped,
+        token,
     }
 }
 
@@ -455,13 +409,18 @@ pub fn free_timeout(callback: Box<(dyn Fn() + Send + Sync + 'static)>, duration:
 /// 
 /// To stop the timeout, call `timeout.stop`.
 pub struct FreeTimeout {
-    // inner: target::FreeTimeout,
-    stopped: Arc<RwLock<bool>>,
+    token: CancellationToken,
 }
 
 impl FreeTimeout {
     pub fn stop(&self) {
-        *self.stopped.write().unwrap() = true;
+        self.token.cancel();
+    }
+
+    /// Returns a future that completes once the timeout is stopped.
+    /// The future does not complete when the action is executed.
+    pub fn stopped(&self) -> WaitForCancellation {
+        self.token.cancelled()
     }
 }
 
@@ -488,23 +447,24 @@ pub macro free_animation_interval {
 
 #[doc(hidden)]
 pub fn free_animation_interval(callback: Box<(dyn Fn(Duration) + Send + Sync + 'static)>, period: Duration) -> FreeInterval {
-    let mut stopped = Arc::new(RwLock::new(false));
+    let token = CancellationToken::new();
+    let mut ticker = animation_ticker(period);
+    let control = ticker.share();
     future::exec({
-        let stopped = Arc::clone(&mut stopped);
+        let token = token.clone();
         async move {
-            let mut ticker = animation_ticker(period);
             ticker.tick().await;
             loop {
-                let delta = ticker.tick().await;
-                if *stopped.read().unwrap() {
-                    break;
+                match futures::future::select(pin!(ticker.tick()), token.cancelled()).await {
+                    futures::future::Either::Left((delta, _)) => callback(delta),
+                    futures::future::Either::Right(_) => break,
                 }
-                callback(delta);
             }
         }
     });
     FreeInterval {
-        stopped,
+        token,
+        ticker: control,
     }
 }
 
@@ -531,23 +491,24 @@ pub macro free_interval {
 
 #[doc(hidden)]
 pub fn free_interval(callback: Box<(dyn Fn(Duration) + Send + Sync + 'static)>, period: Duration) -> FreeInterval {
-    let mut stopped = Arc::new(RwLock::new(false));
+    let token = CancellationToken::new();
+    let mut ticker = ticker(period);
+    let control = ticker.share();
     future::exec({
-        let stopped = Arc::clone(&mut stopped);
+        let token = token.clone();
         async move {
-            let mut ticker = ticker(period);
             ticker.tick().await;
             loop {
-                let delta = ticker.tick().await;
-                if *stopped.read().unwrap() {
-                    break;
+                match futures::future::select(pin!(ticker.tick()), token.cancelled()).await {
+                    futures::future::Either::Left((delta, _)) => callback(delta),
+                    futures::future::Either::Right(_) => break,
                 }
-                callback(delta);
             }
         }
     });
     FreeInterval {
-        stopped,
+        token,
+        ticker: control,
     }
 }
 
@@ -556,11 +517,45 @@ pub fn free_interval(callback: Box<(dyn Fn(Duration) + Send + Sync + 'static)>,
 /// 
 /// To stop the ticker, call `ticker.stop`.
 pub struct FreeInterval {
-    stopped: Arc<RwLock<bool>>,
+    token: CancellationToken,
+    ticker: Ticker,
 }
 
 impl FreeInterval {
     pub fn stop(&self) {
-        *self.stopped.write().unwrap() = true;
+        self.token.cancel();
+    }
+
+    /// Pauses the ticker. See [`Ticker::pause`].
+    pub fn pause(&self) {
+        self.ticker.pause();
+    }
+
+    /// Resumes the ticker. See [`Ticker::resume`].
+    pub fn resume(&self) {
+        self.ticker.resume();
+    }
+
+    /// Schedules the next tick one period from now.
+    pub fn reset(&self) {
+        self.ticker.reset();
+    }
+
+    pub fn period(&self) -> Duration {
+        self.ticker.period()
+    }
+
+    /// Changes the period. See [`Ticker::set_period`].
+    pub fn set_period(&self, period: Duration) {
+        self.ticker.set_period(period);
+    }
+
+    pub fn set_missed_tick_behavior(&self, behavior: MissedTickBehavior) {
+        self.ticker.set_missed_tick_behavior(behavior);
+    }
+
+    /// Returns a future that completes once the ticker is stopped.
+    pub fn stopped(&self) -> WaitForCancellation {
+        self.token.cancelled()
     }
 }
\ No newline at end of file
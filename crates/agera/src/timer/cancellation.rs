@@ -0,0 +1,199 @@
+use std::{collections::BTreeMap, fmt::{Display, Formatter}, future::Future, pin::{pin, Pin}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, PoisonError}, task::{Context, Poll, Waker}};
+use futures::future::{select, Either};
+use super::{wait, Duration};
+
+/// A token through which asynchronous operations, such as
+/// `wait_cancellable()`, are cancelled.
+/// 
+/// Cloning a `CancellationToken` clones a reference to the same token.
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Default, Debug)]
+struct TokenInner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// The wakers of the pending `cancelled()` futures of a token,
+/// keyed so that a future removes its waker when dropped.
+#[derive(Default, Debug)]
+struct Wakers {
+    next_key: u64,
+    entries: BTreeMap<u64, Waker>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token, completing its `cancelled()` futures.
+    /// Cancelling a cancelled token does nothing.
+    pub fn cancel(&self) {
+        if self.inner.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let wakers = std::mem::take(&mut self.inner.wakers.lock().unwrap_or_else(PoisonError::into_inner).entries);
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns a future that completes once the token is cancelled.
+    pub fn cancelled(&self) -> WaitForCancellation {
+        WaitForCancellation { token: self.clone(), key: None }
+    }
+}
+
+/// Future returned by `CancellationToken::cancelled()`.
+pub struct WaitForCancellation {
+    token: CancellationToken,
+    /// The key of the registered waker, if any.
+    key: Option<u64>,
+}
+
+impl Future for WaitForCancellation {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let this = &mut *self;
+        let mut wakers = this.token.inner.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+        // Check again, as the token may have been cancelled before locking.
+        if this.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        match this.key.and_then(|key| wakers.entries.get_mut(&key)) {
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => {
+                let key = wakers.next_key;
+                wakers.next_key += 1;
+                wakers.entries.insert(key, cx.waker().clone());
+                this.key = Some(key);
+            },
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for WaitForCancellation {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.token.inner.wakers.lock().unwrap_or_else(PoisonError::into_inner).entries.remove(&key);
+        }
+    }
+}
+
+/// Error returned by `timeout()` when the duration elapses
+/// before the future completes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Elapsed;
+
+impl Display for Elapsed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Deadline has elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+/// Error returned by `wait_cancellable()` when its token is cancelled.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Awaits a future for at most `duration`, returning `Err(Elapsed)` if the
+/// duration elapses first. The future is dropped in that case.
+/// 
+/// # Examples
+/// 
+/// ```ignore
+/// use agera::timer::*;
+///
+/// async fn example_fn() {
+///     match timeout(Duration::from_secs(10), request()).await {
+///         Ok(response) => {},
+///         Err(Elapsed) => println!("Request timed out"),
+///     }
+/// }
+/// ```
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    match select(pin!(future), pin!(wait(duration))).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(Elapsed),
+    }
+}
+
+/// Asynchronously waits until `duration` has elapsed or `token` is cancelled,
+/// returning `Err(Cancelled)` in the latter case.
+pub async fn wait_cancellable(duration: Duration, token: &CancellationToken) -> Result<(), Cancelled> {
+    if token.is_cancelled() {
+        return Err(Cancelled);
+    }
+    match select(pin!(wait(duration)), token.cancelled()).await {
+        Either::Left(_) => Ok(()),
+        Either::Right(_) => Err(Cancelled),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, pin::pin, sync::{atomic::{AtomicBool, Ordering}, Arc}, task::{Context, Poll}};
+    use futures::task::noop_waker;
+    use crate::timer::{self, testing::MockClock, *};
+
+    #[test]
+    fn test_cancellation() {
+        let clock = MockClock::install();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut timed_out = pin!(timeout(Duration::from_millis(100), futures::future::pending::<()>()));
+        assert_eq!(timed_out.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(timed_out.as_mut().poll(&mut cx), Poll::Ready(Err(Elapsed)));
+
+        let mut completed = pin!(timeout(Duration::from_millis(100), async { 1 }));
+        assert_eq!(completed.as_mut().poll(&mut cx), Poll::Ready(Ok(1)));
+
+        let token = CancellationToken::new();
+        for _ in 0..3 {
+            let mut cancelled = pin!(token.cancelled());
+            assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(cancelled.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert!(token.inner.wakers.lock().unwrap().entries.is_empty());
+        let mut cancellable = pin!(wait_cancellable(Duration::from_secs(1), &token));
+        assert_eq!(cancellable.as_mut().poll(&mut cx), Poll::Pending);
+        token.cancel();
+        assert_eq!(cancellable.as_mut().poll(&mut cx), Poll::Ready(Err(Cancelled)));
+
+        let fired = Arc::new(AtomicBool::new(false));
+        let free_timeout = timer::free_timeout(Box::new({
+            let fired = Arc::clone(&fired);
+            move || fired.store(true, Ordering::SeqCst)
+        }), Duration::from_millis(100));
+        let mut stopped = pin!(free_timeout.stopped());
+        assert_eq!(stopped.as_mut().poll(&mut cx), Poll::Pending);
+        free_timeout.stop();
+        assert_eq!(stopped.as_mut().poll(&mut cx), Poll::Ready(()));
+        clock.advance(Duration::from_millis(100));
+        assert!(!fired.load(Ordering::SeqCst));
+    }
+}
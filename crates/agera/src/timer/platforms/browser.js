@@ -0,0 +1,15 @@
+export function waitInJSPromise(ms) {
+    return new Promise((resolve, _) => {
+        setTimeout(() => {
+            resolve(undefined);
+        }, ms);
+    });
+}
+
+export function animationFrameInJSPromise() {
+    return new Promise((resolve, _) => {
+        requestAnimationFrame(() => {
+            resolve(undefined);
+        });
+    });
+}
@@ -0,0 +1,88 @@
+/*!
+Timing when Agera targets the browser.
+*/
+
+use std::{time::Duration, ops::{Add, AddAssign, Sub, SubAssign}};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "browser.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = waitInJSPromise)]
+    fn wait_in_js_promise(ms: f64) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_name = animationFrameInJSPromise)]
+    fn animation_frame_in_js_promise() -> js_sys::Promise;
+}
+
+pub async fn wait(duration: Duration) {
+    let ms: u32 = duration.as_millis().try_into().expect("Developer has given too large period for wait duration");
+    wasm_bindgen_futures::JsFuture::from(wait_in_js_promise(ms.into())).await.unwrap();
+}
+
+/// Waits for the next animation frame.
+pub async fn animation_frame() {
+    wasm_bindgen_futures::JsFuture::from(animation_frame_in_js_promise()).await.unwrap();
+}
+
+pub async fn wait_until(instant: crate::timer::Instant) {
+    wait(instant.since(crate::timer::Instant::now())).await;
+}
+
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
+pub struct Instant {
+    epoch_ms: u128,
+}
+
+impl Instant {
+    pub fn since(&self, other: Instant) -> Duration {
+        *self - other
+    }
+
+    pub fn now() -> Self {
+        let epoch_ms: u64 = unsafe { js_sys::Date::now().to_int_unchecked() };
+        Self {
+            epoch_ms: epoch_ms.into(),
+        }
+    }
+
+    pub fn try_add(&self, duration: Duration) -> Option<Instant> {
+        Some(Instant { epoch_ms: self.epoch_ms.checked_add(duration.as_millis())? })
+    }
+
+    pub fn try_subtract(&self, duration: Duration) -> Option<Instant> {
+        Some(Instant { epoch_ms: self.epoch_ms.checked_sub(duration.as_millis())? })
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+    fn add(self, rhs: Duration) -> Self::Output {
+        Instant { epoch_ms: self.epoch_ms.checked_add(rhs.as_millis()).expect("Overflow when adding duration to instant") }
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, rhs: Duration) {
+        self.epoch_ms = self.epoch_ms.checked_add(rhs.as_millis()).expect("Overflow when adding duration to instant");
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+    fn sub(self, rhs: Duration) -> Self::Output {
+        Instant { epoch_ms: self.epoch_ms.checked_sub(rhs.as_millis()).expect("Overflow when subtracting duration from instant") }
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+    fn sub(self, rhs: Instant) -> Self::Output {
+        Duration::from_millis(if self.epoch_ms < rhs.epoch_ms { 0 } else { (self.epoch_ms - rhs.epoch_ms).try_into().unwrap_or(u64::MAX) })
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, rhs: Duration) {
+        self.epoch_ms = self.epoch_ms.checked_sub(rhs.as_millis()).expect("Overflow when subtracting duration from instant");
+    }
+}
@@ -0,0 +1,245 @@
+use std::{fmt::{Display, Formatter}, str::FromStr};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use crate::common::*;
+use super::{testing, wait_cancellable, CancellationToken, Cancelled, Duration, WaitForCancellation};
+
+/// Maximum time a schedule waits before comparing the wall-clock time
+/// with its target again, so that changes of the system clock are noticed.
+const WALL_CLOCK_CHECK_PERIOD: Duration = Duration::from_secs(30);
+
+/// A schedule parsed from a cron expression.
+/// 
+/// Expressions consist of five fields, minute, hour, day of month, month and
+/// day of week, optionally preceded by a seconds field:
+/// 
+/// ```
+/// use agera::timer::CronSchedule;
+/// // Every day at 09:00
+/// let daily: CronSchedule = "0 9 * * *".parse().unwrap();
+/// // Every 15 minutes on weekdays
+/// let frequent: CronSchedule = "*/15 * * * MON-FRI".parse().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct CronSchedule {
+    cron: croner::Cron,
+    expression: String,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, CronSyntaxError> {
+        let cron = croner::Cron::new(expression)
+            .with_seconds_optional()
+            .parse()
+            .map_err(|error| CronSyntaxError { message: error.to_string() })?;
+        Ok(Self { cron, expression: expression.to_owned() })
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the first occurrence later than `time`, in the time zone of `time`.
+    /// Local times skipped by a daylight saving transition resolve to the
+    /// first valid time after the gap; local times occurring twice match once.
+    pub fn next_after<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.cron.find_next_occurrence(time, false).ok()
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronSyntaxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Error resulted from parsing a `CronSchedule` from a string.
+#[derive(Clone, Debug)]
+pub struct CronSyntaxError {
+    message: String,
+}
+
+impl Display for CronSyntaxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid cron expression: {}", self.message)
+    }
+}
+
+impl std::error::Error for CronSyntaxError {}
+
+/// Returns the current wall-clock time, following the
+/// mock clock installed on the current thread, if any.
+pub fn wall_clock_now() -> DateTime<Utc> {
+    match testing::current() {
+        Some(clock) => clock.wall_now(),
+        None => Utc::now(),
+    }
+}
+
+/// Asynchronously waits until the wall-clock time reaches `target`,
+/// following changes of the system clock.
+async fn wait_for_wall_clock(target: DateTime<Utc>, token: &CancellationToken) -> Result<(), Cancelled> {
+    loop {
+        let Ok(remaining) = (target - wall_clock_now()).to_std() else {
+            return Ok(());
+        };
+        if remaining.is_zero() {
+            return Ok(());
+        }
+        wait_cancellable(remaining.min(WALL_CLOCK_CHECK_PERIOD), token).await?;
+    }
+}
+
+/// Executes an action at each occurrence of a cron schedule
+/// in the local time zone. Returns a `FreeSchedule` object
+/// with a `stop()` method.
+/// 
+/// Occurrences are determined by the wall-clock time: when the system
+/// clock is set back, occurrences that already ran do not run again, and
+/// when it is set forward past several occurrences, the action runs once.
+/// 
+/// ```ignore
+/// use agera::timer::*;
+/// let reminder = free_cron("0 9 * * *".parse().unwrap(), Box::new(|| {
+///     // Action
+/// }));
+/// ```
+pub fn free_cron(schedule: CronSchedule, callback: Box<dyn Fn() + Send + Sync + 'static>) -> FreeSchedule {
+    free_cron_in(schedule, Local, callback)
+}
+
+/// Executes an action at each occurrence of a cron schedule
+/// in the given time zone. See [`free_cron`].
+pub fn free_cron_in<Tz>(schedule: CronSchedule, timezone: Tz, callback: Box<dyn Fn() + Send + Sync + 'static>) -> FreeSchedule
+    where Tz: TimeZone + 'static
+{
+    let token = CancellationToken::new();
+    future::exec({
+        let token = token.clone();
+        async move {
+            let mut after = wall_clock_now().with_timezone(&timezone);
+            while let Some(next) = schedule.next_after(&after) {
+                if wait_for_wall_clock(next.with_timezone(&Utc), &token).await.is_err() {
+                    break;
+                }
+                callback();
+                // Skip the occurrences passed while the clock was set forward.
+                let now = wall_clock_now().with_timezone(&timezone);
+                after = if now > next { now } else { next };
+            }
+        }
+    });
+    FreeSchedule { token }
+}
+
+/// Executes an action once the wall-clock time reaches `time`.
+/// Returns a `FreeSchedule` object with a `stop()` method.
+/// The action runs immediately if `time` has passed.
+pub fn free_at<Tz: TimeZone>(time: DateTime<Tz>, callback: Box<dyn Fn() + Send + Sync + 'static>) -> FreeSchedule {
+    let token = CancellationToken::new();
+    let time = time.with_timezone(&Utc);
+    future::exec({
+        let token = token.clone();
+        async move {
+            if wait_for_wall_clock(time, &token).await.is_ok() {
+                callback();
+            }
+        }
+    });
+    FreeSchedule { token }
+}
+
+/// A schedule that can be stopped at anytime, returned
+/// from [`free_cron`], [`free_cron_in`] and [`free_at`].
+pub struct FreeSchedule {
+    token: CancellationToken,
+}
+
+impl FreeSchedule {
+    pub fn stop(&self) {
+        self.token.cancel();
+    }
+
+    /// Returns a future that completes once the schedule is stopped.
+    pub fn stopped(&self) -> WaitForCancellation {
+        self.token.cancelled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::America::New_York;
+    use crate::timer::{testing::MockClock, *};
+
+    #[test]
+    fn test_schedule() {
+        let daily: CronSchedule = "0 9 * * *".parse().unwrap();
+        let next = daily.next_after(&Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap());
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2024, 3, 2, 9, 0, 0).unwrap()));
+        assert!("0 25 * * *".parse::<CronSchedule>().is_err());
+
+        let clock = MockClock::install();
+        clock.set_wall_clock(Utc.with_ymd_and_hms(2024, 3, 1, 8, 59, 0).unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+        let schedule = free_cron_in(daily, Utc, Box::new({
+            let count = Arc::clone(&count);
+            move || { count.fetch_add(1, Ordering::SeqCst); }
+        }));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Setting the clock back does not run the occurrence again.
+        clock.set_wall_clock(Utc.with_ymd_and_hms(2024, 3, 1, 8, 59, 0).unwrap());
+        clock.advance(Duration::from_secs(120));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // Setting the clock forward runs the next occurrence.
+        clock.set_wall_clock(Utc.with_ymd_and_hms(2024, 3, 2, 8, 59, 30).unwrap());
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        schedule.stop();
+
+        let fired = Arc::new(AtomicUsize::new(0));
+        free_at(Utc.with_ymd_and_hms(2024, 3, 2, 12, 0, 0).unwrap(), Box::new({
+            let fired = Arc::clone(&fired);
+            move || { fired.fetch_add(1, Ordering::SeqCst); }
+        }));
+        clock.advance(Duration::from_secs(3 * 60 * 60));
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_schedule_daylight_saving() {
+        let nightly: CronSchedule = "30 2 * * *".parse().unwrap();
+        let early: CronSchedule = "30 1 * * *".parse().unwrap();
+        let new_york = |year, month, day, hour, minute| New_York.with_ymd_and_hms(year, month, day, hour, minute, 0).earliest().unwrap();
+
+        // 02:30 is skipped on 2024-03-10, when clocks go from 02:00 to 03:00.
+        let next = nightly.next_after(&new_york(2024, 3, 10, 0, 0)).unwrap();
+        assert_eq!(next, new_york(2024, 3, 10, 3, 0));
+        assert_eq!(next.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 3, 10, 7, 0, 0).unwrap());
+        assert_eq!(nightly.next_after(&next), Some(new_york(2024, 3, 11, 2, 30)));
+
+        // 01:30 occurs twice on 2024-11-03, when clocks go from 02:00 back to 01:00.
+        let next = early.next_after(&new_york(2024, 11, 3, 0, 0)).unwrap();
+        assert_eq!(next.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap());
+        assert_eq!(early.next_after(&next), Some(new_york(2024, 11, 4, 1, 30)));
+
+        // A schedule in the zone runs once across the transition.
+        let clock = MockClock::install();
+        clock.set_wall_clock(Utc.with_ymd_and_hms(2024, 11, 3, 5, 0, 0).unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+        let schedule = free_cron_in(early, New_York, Box::new({
+            let count = Arc::clone(&count);
+            move || { count.fetch_add(1, Ordering::SeqCst); }
+        }));
+        clock.advance(Duration::from_secs(60 * 60));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        clock.advance(Duration::from_secs(60 * 60));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        schedule.stop();
+    }
+}
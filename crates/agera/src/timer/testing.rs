@@ -0,0 +1,367 @@
+/*!
+A controllable clock for deterministic tests of timed logic.
+
+While a `MockClock` is installed on a thread, `Instant::now()`, waits,
+tickers, free timeouts, free intervals and schedules created on that thread follow
+the mock clock instead of the host environment. Time only advances
+through `MockClock::advance()` and `MockClock::run_until_idle()`.
+
+Futures spawned through `future::exec()` on that thread, including those
+of free timeouts and free intervals, are run by the mock clock itself
+while advancing. Other futures are woken by the mock clock and must be
+polled by the test.
+
+```ignore
+use agera::timer::{*, testing::MockClock};
+
+let clock = MockClock::install();
+let timeout = free_timeout(Box::new(|| println!("Fired")), Duration::from_millis(100));
+clock.advance(Duration::from_millis(100)); // prints "Fired"
+```
+*/
+
+use std::{cell::RefCell, collections::BTreeMap, future::Future, pin::Pin, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, PoisonError}, task::{Context, Poll, Waker}};
+use chrono::{DateTime, Utc};
+use futures::task::{waker, ArcWake};
+use super::{platforms, Duration, Instant};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<ClockInner>>> = const { RefCell::new(None) };
+    static TASKS: RefCell<Vec<MockTask>> = const { RefCell::new(vec![]) };
+}
+
+/// Returns the mock clock installed on the current thread.
+pub(super) fn current() -> Option<Arc<ClockInner>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Runs a future spawned through `future::exec()` through the mock clock
+/// installed on the current thread, if any. Otherwise returns the future.
+pub(crate) fn spawn<F: Future<Output = ()> + 'static>(future: F) -> Option<F> {
+    if current().is_none() {
+        return Some(future);
+    }
+    TASKS.with(|tasks| tasks.borrow_mut().push(MockTask {
+        future: Box::pin(future),
+        woken: Arc::new(TaskWaker(AtomicBool::new(true))),
+    }));
+    None
+}
+
+struct MockTask {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    woken: Arc<TaskWaker>,
+}
+
+struct TaskWaker(AtomicBool);
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Polls the woken tasks until none is woken.
+fn run_tasks() {
+    loop {
+        let mut tasks = TASKS.with(|tasks| std::mem::take(&mut *tasks.borrow_mut()));
+        let mut progressed = false;
+        tasks.retain_mut(|task| {
+            if !task.woken.0.swap(false, Ordering::SeqCst) {
+                return true;
+            }
+            progressed = true;
+            let waker = waker(Arc::clone(&task.woken));
+            task.future.as_mut().poll(&mut Context::from_waker(&waker)).is_pending()
+        });
+        // Keep the tasks spawned while polling.
+        TASKS.with(|current| {
+            let mut current = current.borrow_mut();
+            tasks.append(&mut current);
+            *current = tasks;
+        });
+        if !progressed {
+            break;
+        }
+    }
+}
+
+/// A mock clock, installed on the current thread until dropped.
+pub struct MockClock {
+    inner: Arc<ClockInner>,
+    previous: Option<Arc<ClockInner>>,
+}
+
+impl MockClock {
+    /// Installs a mock clock on the current thread, starting
+    /// at the current instant of the host environment.
+    pub fn install() -> Self {
+        let inner = Arc::new(ClockInner {
+            start: Instant { inner: platforms::Instant::now() },
+            state: Mutex::new(ClockState {
+                elapsed: Duration::ZERO,
+                wall_start: Utc::now(),
+                next_id: 0,
+                timers: BTreeMap::new(),
+            }),
+        });
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(Arc::clone(&inner)));
+        Self { inner, previous }
+    }
+
+    /// The current instant of the mock clock.
+    pub fn now(&self) -> Instant {
+        self.inner.now()
+    }
+
+    /// The wall-clock time of the mock clock, which starts at the current
+    /// wall-clock time of the host environment and advances with the clock.
+    pub fn wall_clock_now(&self) -> DateTime<Utc> {
+        self.inner.wall_now()
+    }
+
+    /// Sets the wall-clock time without advancing the clock,
+    /// simulating a change of the system clock.
+    pub fn set_wall_clock(&self, time: DateTime<Utc>) {
+        let mut state = self.inner.state();
+        state.wall_start = time - chrono::Duration::from_std(state.elapsed).unwrap_or(chrono::Duration::zero());
+    }
+
+    /// The time elapsed since the mock clock was installed.
+    pub fn elapsed(&self) -> Duration {
+        self.inner.state().elapsed
+    }
+
+    /// The number of pending timers, including waits and ticks.
+    pub fn pending_timers(&self) -> usize {
+        self.inner.state().timers.len()
+    }
+
+    /// The number of spawned tasks that have not finished.
+    pub fn pending_tasks(&self) -> usize {
+        TASKS.with(|tasks| tasks.borrow().len())
+    }
+
+    /// Advances the clock by `duration`, firing due timers in order of their deadline
+    /// and running the spawned tasks they wake. The clock is set to the deadline
+    /// of each timer as it fires.
+    pub fn advance(&self, duration: Duration) {
+        let target = self.elapsed() + duration;
+        self.inner.advance_to(target);
+    }
+
+    /// Advances the clock through pending timers until none remain,
+    /// disregarding the ticks of tickers and free intervals, which never finish.
+    pub fn run_until_idle(&self) {
+        loop {
+            run_tasks();
+            let deadline = self.inner.state().timers.iter()
+                .find(|(_, timer)| !timer.periodic)
+                .map(|((deadline, _), _)| *deadline);
+            let Some(deadline) = deadline else {
+                break;
+            };
+            self.inner.advance_to(deadline);
+        }
+    }
+}
+
+impl Drop for MockClock {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        if previous.is_none() {
+            let tasks = TASKS.with(|tasks| std::mem::take(&mut *tasks.borrow_mut()));
+            drop(tasks);
+        }
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+pub(super) struct ClockInner {
+    start: Instant,
+    state: Mutex<ClockState>,
+}
+
+struct ClockState {
+    elapsed: Duration,
+    /// The wall-clock time when `elapsed` was zero.
+    wall_start: DateTime<Utc>,
+    next_id: u64,
+    /// Timers by deadline, relative to the start of the clock.
+    timers: BTreeMap<(Duration, u64), Timer>,
+}
+
+struct Timer {
+    action: Box<dyn FnOnce() + Send>,
+    periodic: bool,
+}
+
+impl ClockInner {
+    fn state(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn now(&self) -> Instant {
+        self.start + self.state().elapsed
+    }
+
+    pub(super) fn wall_now(&self) -> DateTime<Utc> {
+        let state = self.state();
+        state.wall_start + chrono::Duration::from_std(state.elapsed).unwrap_or(chrono::Duration::zero())
+    }
+
+    fn advance_to(&self, target: Duration) {
+        loop {
+            run_tasks();
+            // Fire timers one at a time, as actions may add or remove timers.
+            let timer = {
+                let mut state = self.state();
+                match state.timers.first_key_value() {
+                    Some(((deadline, _), _)) if *deadline <= target => {
+                        let (deadline, timer) = state.timers.pop_first().map(|((deadline, _), timer)| (deadline, timer)).unwrap();
+                        state.elapsed = state.elapsed.max(deadline);
+                        Some(timer)
+                    },
+                    _ => None,
+                }
+            };
+            let Some(timer) = timer else {
+                break;
+            };
+            (timer.action)();
+        }
+        {
+            let mut state = self.state();
+            state.elapsed = state.elapsed.max(target);
+        }
+        run_tasks();
+    }
+
+    fn schedule(&self, deadline: Instant, periodic: bool, action: Box<dyn FnOnce() + Send>) -> (Duration, u64) {
+        let deadline = deadline.since(self.start);
+        let mut state = self.state();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.timers.insert((deadline, id), Timer { action, periodic });
+        (deadline, id)
+    }
+
+    fn cancel(&self, key: (Duration, u64)) {
+        self.state().timers.remove(&key);
+    }
+
+    /// Returns a future that completes once the clock reaches `deadline`.
+    /// Periodic waits are disregarded by `MockClock::run_until_idle()`.
+    pub(super) fn wait_until(self: &Arc<Self>, deadline: Instant, periodic: bool) -> MockWait {
+        if deadline <= self.now() {
+            return MockWait {
+                clock: Arc::clone(self),
+                key: None,
+                state: Arc::new(Mutex::new(WaitState { done: true, waker: None })),
+            };
+        }
+        let state = Arc::new(Mutex::new(WaitState { done: false, waker: None }));
+        let key = self.schedule(deadline, periodic, Box::new({
+            let state = Arc::clone(&state);
+            move || {
+                let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                state.done = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        }));
+        MockWait { clock: Arc::clone(self), key: Some(key), state }
+    }
+}
+
+struct WaitState {
+    done: bool,
+    waker: Option<Waker>,
+}
+
+/// A wait on a mock clock.
+pub(super) struct MockWait {
+    clock: Arc<ClockInner>,
+    key: Option<(Duration, u64)>,
+    state: Arc<Mutex<WaitState>>,
+}
+
+impl Future for MockWait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.done {
+            return Poll::Ready(());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for MockWait {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.clock.cancel(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, pin::pin, sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex}, task::{Context, Poll}};
+    use futures::task::noop_waker;
+    use crate::{events::EventEmitter, timer::{self, testing::MockClock, Duration, Instant}};
+
+    #[test]
+    fn test_mock_clock() {
+        let clock = MockClock::install();
+        let start = Instant::now();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        // Waits
+        let mut wait = pin!(timer::wait(Duration::from_millis(100)));
+        assert_eq!(wait.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(Duration::from_millis(99));
+        assert_eq!(wait.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(wait.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(Instant::now().since(start), Duration::from_millis(100));
+
+        // Free timeouts and intervals
+        let count = Arc::new(AtomicUsize::new(0));
+        let timeout = timer::free_timeout(Box::new({
+            let count = Arc::clone(&count);
+            move || { count.fetch_add(1, Ordering::SeqCst); }
+        }), Duration::from_millis(50));
+        let interval = timer::free_interval(Box::new({
+            let count = Arc::clone(&count);
+            move |_| { count.fetch_add(10, Ordering::SeqCst); }
+        }), Duration::from_millis(20));
+        clock.run_until_idle();
+        assert_eq!(count.load(Ordering::SeqCst), 21);
+        assert_eq!(clock.elapsed(), Duration::from_millis(150));
+        interval.stop();
+        timeout.stop();
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(count.load(Ordering::SeqCst), 21);
+
+        // Debounce
+        let emitter = EventEmitter::<u32>::new();
+        let debounced = emitter.debounce(Duration::from_millis(10));
+        let received = Arc::new(Mutex::new(vec![]));
+        debounced.listener({
+            let received = Arc::clone(&received);
+            move |value| received.lock().unwrap().push(value)
+        });
+        emitter.emit(1);
+        clock.advance(Duration::from_millis(5));
+        emitter.emit(2);
+        clock.advance(Duration::from_millis(5));
+        assert!(received.lock().unwrap().is_empty());
+        clock.run_until_idle();
+        assert_eq!(*received.lock().unwrap(), [2]);
+    }
+}
@@ -0,0 +1,257 @@
+use std::{pin::pin, sync::{Arc, Mutex, MutexGuard, PoisonError}};
+use futures::future::{select, Either};
+use crate::platforms::if_browser;
+use super::{testing, CancellationToken, Duration, Instant};
+
+/// Indicates how a [`Ticker`] behaves when ticks are missed, such as
+/// when the application was in the background or the ticker was
+/// not awaited for longer than its period.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MissedTickBehavior {
+    /// Ticks immediately until caught up with the original schedule.
+    Burst,
+    /// Schedules the next tick one period after the late tick.
+    Delay,
+    /// Skips the missed ticks, ticking at the next instant of the original schedule.
+    Skip,
+}
+
+/// Ticker returned by [`ticker`](super::ticker),
+/// [`ticker_at`](super::ticker_at), [`animation_ticker`](super::animation_ticker) and
+/// [`animation_ticker_at`](super::animation_ticker_at).
+/// 
+/// The missed tick behavior defaults to `MissedTickBehavior::Burst` for
+/// regular tickers and to `MissedTickBehavior::Skip` for animation tickers.
+#[derive(Debug)]
+pub struct Ticker {
+    shared: Arc<TickerShared>,
+}
+
+#[derive(Debug)]
+struct TickerShared {
+    for_animation: bool,
+    state: Mutex<TickerState>,
+}
+
+#[derive(Debug)]
+struct TickerState {
+    period: Duration,
+    behavior: MissedTickBehavior,
+    next: Instant,
+    last_tick: Option<Instant>,
+    paused_at: Option<Instant>,
+    /// Cancelled whenever the schedule changes, waking a pending tick.
+    changed: CancellationToken,
+}
+
+impl TickerState {
+    fn notify(&mut self) {
+        std::mem::take(&mut self.changed).cancel();
+    }
+}
+
+impl Ticker {
+    pub(super) fn new(start: Instant, period: Duration, for_animation: bool) -> Self {
+        assert!(!period.is_zero(), "agera::timer ticker must be created with non-zero period");
+        Self {
+            shared: Arc::new(TickerShared {
+                for_animation,
+                state: Mutex::new(TickerState {
+                    period,
+                    behavior: if for_animation { MissedTickBehavior::Skip } else { MissedTickBehavior::Burst },
+                    next: start,
+                    last_tick: None,
+                    paused_at: None,
+                    changed: CancellationToken::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Returns a ticker sharing the schedule of this ticker, used
+    /// for controlling the ticker of a free interval.
+    pub(super) fn share(&self) -> Self {
+        Self { shared: Arc::clone(&self.shared) }
+    }
+
+    fn state(&self) -> MutexGuard<'_, TickerState> {
+        self.shared.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Completes when the next instant in the ticker has been reached,
+    /// yielding the time elapsed since the last tick, excluding the
+    /// time the ticker was paused. The first tick yields zero.
+    pub async fn tick(&mut self) -> Duration {
+        loop {
+            let (deadline, changed) = {
+                let state = self.state();
+                (state.paused_at.is_none().then_some(state.next), state.changed.clone())
+            };
+            let Some(deadline) = deadline else {
+                changed.cancelled().await;
+                continue;
+            };
+            if let Either::Left(_) = select(pin!(wait_until_periodic(deadline)), changed.cancelled()).await {
+                break;
+            }
+        }
+        if self.shared.for_animation {
+            if_browser! {{
+                super::platforms::browser::animation_frame().await;
+            }}
+        }
+
+        let now = Instant::now();
+        let mut state = self.state();
+        let delta = state.last_tick.map(|last_tick| now.since(last_tick)).unwrap_or(Duration::ZERO);
+        state.last_tick = Some(now);
+        let period = state.period;
+        state.next = match state.behavior {
+            MissedTickBehavior::Burst => state.next + period,
+            MissedTickBehavior::Delay => now + period,
+            MissedTickBehavior::Skip => {
+                let next = state.next + period;
+                if next > now {
+                    next
+                } else {
+                    let missed = now.since(next).as_nanos() / period.as_nanos() + 1;
+                    next + Duration::from_nanos((period.as_nanos() * missed).try_into().unwrap_or(u64::MAX))
+                }
+            },
+        };
+        delta
+    }
+
+    pub fn period(&self) -> Duration {
+        self.state().period
+    }
+
+    /// Changes the period, scheduling the next tick one
+    /// period after the last tick.
+    /// 
+    /// # Panics
+    ///
+    /// This method panics if `period` is zero.
+    pub fn set_period(&self, period: Duration) {
+        assert!(!period.is_zero(), "agera::timer ticker must have non-zero period");
+        let mut state = self.state();
+        state.period = period;
+        if let Some(last_tick) = state.last_tick {
+            state.next = last_tick + period;
+        }
+        state.notify();
+    }
+
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.state().behavior
+    }
+
+    pub fn set_missed_tick_behavior(&self, behavior: MissedTickBehavior) {
+        self.state().behavior = behavior;
+    }
+
+    /// Schedules the next tick one period from now.
+    pub fn reset(&self) {
+        let now = Instant::now();
+        let mut state = self.state();
+        state.next = now + state.period;
+        if state.paused_at.is_some() {
+            state.paused_at = Some(now);
+        }
+        state.notify();
+    }
+
+    /// Pauses the ticker. No ticks complete until the ticker is resumed.
+    pub fn pause(&self) {
+        let mut state = self.state();
+        if state.paused_at.is_none() {
+            state.paused_at = Some(Instant::now());
+            state.notify();
+        }
+    }
+
+    /// Resumes the ticker, postponing its schedule by the time it was paused.
+    pub fn resume(&self) {
+        let now = Instant::now();
+        let mut state = self.state();
+        if let Some(paused_at) = state.paused_at.take() {
+            let paused = now.since(paused_at);
+            state.next += paused;
+            state.last_tick = state.last_tick.map(|last_tick| last_tick + paused);
+            state.notify();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state().paused_at.is_some()
+    }
+}
+
+/// Waits until `deadline`, marking the wait as periodic
+/// for `MockClock::run_until_idle()`.
+async fn wait_until_periodic(deadline: Instant) {
+    if let Some(clock) = testing::current() {
+        clock.wait_until(deadline, true).await;
+        return;
+    }
+    super::wait_until(deadline).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, pin::pin, task::{Context, Poll}};
+    use futures::task::noop_waker;
+    use crate::timer::{testing::MockClock, *};
+
+    #[test]
+    fn test_ticker() {
+        let clock = MockClock::install();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut ticker = ticker(Duration::from_millis(10));
+        let mut tick = |ticker: &mut Ticker| pin!(ticker.tick()).poll(&mut cx);
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::ZERO));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+
+        // Skip
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        clock.advance(Duration::from_millis(35));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(35)));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(5)));
+
+        // Burst
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+        clock.advance(Duration::from_millis(30));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(30)));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::ZERO));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::ZERO));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+
+        // Delay
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        clock.advance(Duration::from_millis(25));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(25)));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+        clock.advance(Duration::from_millis(9));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(10)));
+
+        // Pausing postpones the schedule.
+        ticker.pause();
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+        ticker.resume();
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(10)));
+
+        // Changing the period
+        ticker.set_period(Duration::from_millis(50));
+        clock.advance(Duration::from_millis(49));
+        assert_eq!(tick(&mut ticker), Poll::Pending);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(tick(&mut ticker), Poll::Ready(Duration::from_millis(50)));
+    }
+}
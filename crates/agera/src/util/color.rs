@@ -1,4 +1,5 @@
 use std::{fmt::{Debug, Display}, str::FromStr};
+use crate::common::*;
 
 /// Represents a color containing red, green, blue and alpha channels.
 ///
@@ -83,6 +84,31 @@ impl Color {
     }
 }
 
+/// Serializes as a `[red, green, blue, alpha]` sequence of channels
+/// in the range between 0 and 1, which preserves the channels exactly.
+impl Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.0.red, self.0.green, self.0.blue, self.0.alpha].serialize(serializer)
+    }
+}
+
+/// Deserializes from a `[red, green, blue, alpha]` sequence of channels
+/// or from any CSS color string supported by `FromStr`.
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Channels([f32; 4]),
+            Css(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Channels([red, green, blue, alpha]) => Ok(Self::new(red, green, blue, alpha)),
+            Repr::Css(string) => string.parse().map_err(|_| serde::de::Error::custom(format!("Invalid color: '{string}'"))),
+        }
+    }
+}
+
 impl FromStr for Color {
     type Err = ColorSyntaxError;
 
@@ -92,7 +118,7 @@ impl FromStr for Color {
 }
 
 /// Represents red, green, blue and alpha offsets in the range between -255 and 255.
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ColorOffsets {
     red: i32,
     green: i32,
@@ -5,6 +5,10 @@ pub fn exec<F>(future: F)
 where
     F: Future<Output = ()> + 'static,
 {
+    // Futures spawned while a mock clock is installed are run by the mock clock.
+    let Some(future) = crate::timer::testing::spawn(future) else {
+        return;
+    };
     if_native_platform! {{
         crate::application::assert_bootstrapped!();
         tokio::task::spawn_local(future);
@@ -1 +1,104 @@
-#![feature(proc_macro_diagnostic)]
\ No newline at end of file
+#![feature(proc_macro_diagnostic)]
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse::{Parse, ParseStream}, parse_macro_input, punctuated::Punctuated, Attribute, Ident, Token, Type, Visibility};
+
+/// Generates observable accessors for fields stored through `class!`.
+/// See `agera::events::observable_properties!` for documentation.
+#[proc_macro]
+pub fn observable_properties(input: TokenStream) -> TokenStream {
+    let ObservableProperties { agera_crate, class, properties } = parse_macro_input!(input as ObservableProperties);
+    let agera_crate = agera_crate.unwrap_or(quote! { ::agera });
+
+    let mut methods = proc_macro2::TokenStream::new();
+    for ObservableProperty { attributes, visibility, name, field_type, storage } in properties {
+        let setter = format_ident!("set_{}", name);
+        let emitter = format_ident!("on_{}_changed", name);
+        let property = format_ident!("{}_property", name);
+        let storage_setter = format_ident!("set_{}", storage);
+        let name_string = name.to_string();
+        let emitter_doc = format!("Emits when `{name_string}` changes, with the old and new values.");
+        let property_doc = format!("The `{name_string}` property, for use with `agera::binding`.");
+        methods.extend(quote! {
+            #(#attributes)*
+            #visibility fn #name(&self) -> #field_type {
+                self.#storage()
+            }
+
+            #(#attributes)*
+            #visibility fn #setter(&self, value: #field_type) -> Self {
+                let old_value = self.#storage();
+                if old_value != value {
+                    self.#storage_setter(::std::clone::Clone::clone(&value));
+                    #agera_crate::events::notify_property_change::<#field_type>(::std::convert::AsRef::<#agera_crate::util::inheritance::Node>::as_ref(self), #name_string, old_value, value);
+                }
+                ::std::clone::Clone::clone(self)
+            }
+
+            #[doc = #emitter_doc]
+            #visibility fn #emitter(&self) -> ::std::sync::Arc<#agera_crate::events::EventEmitter<#agera_crate::events::PropertyChange<#field_type>>> {
+                #agera_crate::events::property_emitter::<#field_type>(::std::convert::AsRef::<#agera_crate::util::inheritance::Node>::as_ref(self), #name_string)
+            }
+
+            #[doc = #property_doc]
+            #visibility fn #property(&self) -> #agera_crate::binding::Property<#field_type> {
+                #agera_crate::binding::Property::new(self, Self::#name, Self::#setter, Self::#emitter)
+            }
+        });
+    }
+
+    quote! {
+        impl #class {
+            #methods
+        }
+    }.into()
+}
+
+struct ObservableProperties {
+    agera_crate: Option<proc_macro2::TokenStream>,
+    class: Type,
+    properties: Punctuated<ObservableProperty, Token![,]>,
+}
+
+impl Parse for ObservableProperties {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // An optional crate path, used from within the agera crate: `use crate;`
+        let agera_crate = if input.peek(Token![use]) {
+            input.parse::<Token![use]>()?;
+            let path = input.parse::<syn::Path>()?;
+            input.parse::<Token![;]>()?;
+            Some(quote! { #path })
+        } else {
+            None
+        };
+        input.parse::<Token![impl]>()?;
+        let class = input.parse::<Type>()?;
+        let content;
+        syn::braced!(content in input);
+        let properties = content.parse_terminated(ObservableProperty::parse, Token![,])?;
+        Ok(Self { agera_crate, class, properties })
+    }
+}
+
+/// A property in the form `pub name: Type => storage`.
+struct ObservableProperty {
+    attributes: Vec<Attribute>,
+    visibility: Visibility,
+    name: Ident,
+    field_type: Type,
+    storage: Ident,
+}
+
+impl Parse for ObservableProperty {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attributes = input.call(Attribute::parse_outer)?;
+        let visibility = input.parse::<Visibility>()?;
+        let name = input.parse::<Ident>()?;
+        input.parse::<Token![:]>()?;
+        let field_type = input.parse::<Type>()?;
+        input.parse::<Token![=>]>()?;
+        let storage = input.parse::<Ident>()?;
+        Ok(Self { attributes, visibility, name, field_type, storage })
+    }
+}